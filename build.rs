@@ -1,5 +1,10 @@
 use flate2::{write::GzEncoder, Compression};
-use std::{collections::HashMap, io, path::Path};
+use std::{io, path::Path};
+
+#[allow(dead_code)]
+#[path = "src/pack_manifest.rs"]
+mod pack_manifest;
+use pack_manifest::{parse_frame_name, PackManifest};
 
 // only include winres if compiling for Windows
 #[cfg(target_os = "windows")]
@@ -38,70 +43,135 @@ fn main() -> io::Result<()> {
         res.compile()?;
     }
 
-    generate_icon_resources()?;
+    if let Err(e) = generate_icon_resources() {
+        eprintln!("error: {}", e);
+        std::process::exit(1);
+    }
     Ok(())
 }
 
-fn generate_icon_resources() -> io::Result<()> {
+fn generate_icon_resources() -> Result<(), String> {
     let out_dir = std::env::var_os("OUT_DIR").unwrap();
     let dest_path = Path::new(&out_dir).join("icon_data.rs");
 
-    // Define icon configurations that match the icon manager structure
-    let icon_configs = [
-        ("cat", [("light", 5), ("dark", 5)]),
-        ("parrot", [("light", 10), ("dark", 10)]),
-        ("sleep", [("light", 3), ("dark", 3)]),
-    ];
+    println!("cargo:rerun-if-changed=assets");
+    println!("cargo:rerun-if-changed=src/pack_manifest.rs");
+    println!("cargo:rerun-if-changed=build.rs");
+    // Keep GIT_HASH current now that the script no longer reruns on every change
+    for git_path in [".git/HEAD", ".git/refs/heads", ".git/packed-refs"] {
+        if Path::new(git_path).exists() {
+            println!("cargo:rerun-if-changed={}", git_path);
+        }
+    }
+
+    let packs = discover_packs(Path::new("assets"))?;
+    if packs.is_empty() {
+        return Err("no icon packs found in assets/".to_string());
+    }
 
     // Concatenate ALL icons into one big chunk for maximum compression
-    let (compressed_data, icon_metadata) = generate_all_icons_compressed(&icon_configs)?;
+    let (compressed_data, icon_metadata) =
+        generate_all_icons_compressed(&packs).map_err(|e| e.to_string())?;
 
     // Generate code with single compressed chunk
-    let code = generate_single_chunk_module(&compressed_data, &icon_metadata);
+    let code = generate_single_chunk_module(&compressed_data, &icon_metadata, &packs);
+
+    std::fs::write(&dest_path, code.as_bytes()).map_err(|e| e.to_string())
+}
+
+/// A built-in pack: directory name, manifest text and parsed manifest
+type Pack = (String, String, PackManifest);
+
+/// Every directory under `assets` that has a `pack.toml` or frames named
+/// `<theme>_<dir>_<n>.ico` is a built-in pack. Other directories are skipped.
+fn discover_packs(assets: &Path) -> Result<Vec<Pack>, String> {
+    let mut dirs = std::fs::read_dir(assets)
+        .map_err(|e| format!("{}: {}", assets.display(), e))?
+        .filter_map(Result::ok)
+        .filter(|entry| entry.path().is_dir())
+        .filter_map(|entry| entry.file_name().into_string().ok())
+        .collect::<Vec<_>>();
+    dirs.sort();
+
+    let mut packs = Vec::new();
+    for name in dirs {
+        let dir = assets.join(&name);
+        if !is_pack_dir(&name, &dir) {
+            continue;
+        }
+
+        let manifest_path = dir.join("pack.toml");
+        let manifest =
+            PackManifest::load(&name, &dir).map_err(|e| format!("{}: {}", dir.display(), e))?;
+        let missing = manifest.missing_frames(&dir);
+        if !missing.is_empty() {
+            return Err(format!(
+                "{}: missing frames {}",
+                dir.display(),
+                missing.join(", ")
+            ));
+        }
+        // Packs described by their file names get a generated manifest
+        let text = if manifest_path.exists() {
+            std::fs::read_to_string(&manifest_path).map_err(|e| e.to_string())?
+        } else {
+            toml::to_string(&manifest).map_err(|e| e.to_string())?
+        };
+        packs.push((name, text, manifest));
+    }
+    Ok(packs)
+}
 
-    std::fs::write(&dest_path, code.as_bytes())
+fn is_pack_dir(name: &str, dir: &Path) -> bool {
+    dir.join("pack.toml").exists()
+        || std::fs::read_dir(dir).is_ok_and(|entries| {
+            entries.filter_map(Result::ok).any(|entry| {
+                entry
+                    .file_name()
+                    .to_str()
+                    .is_some_and(|file| parse_frame_name(name, file).is_some())
+            })
+        })
 }
 
 // Icon metadata for the single compressed chunk
 #[derive(Debug)]
 struct IconGroupMetadata {
     icon_name: String,
+    state: String,
     theme: String,
     offset: usize,
     sizes: Vec<usize>,
 }
 
-fn generate_all_icons_compressed(
-    icon_configs: &[(&str, [(&str, usize); 2])],
-) -> io::Result<(String, Vec<IconGroupMetadata>)> {
+fn generate_all_icons_compressed(packs: &[Pack]) -> io::Result<(String, Vec<IconGroupMetadata>)> {
     let mut all_icons_data = Vec::new();
     let mut metadata = Vec::new();
 
-    // Collect all icon data
-    for (icon_name, themes) in icon_configs.iter() {
-        for (theme, count) in themes.iter() {
-            let base = std::fs::canonicalize(Path::new("assets").join(icon_name))?;
-            let icon_file_names = (0..*count)
-                .map(|i| format!("{}_{}_{}", theme, icon_name, i))
-                .collect::<Vec<_>>();
-
-            let mut group_sizes = Vec::new();
-            let group_offset = all_icons_data.len();
-
-            // Read all icons for this group
-            for name in &icon_file_names {
-                let icon_path = base.join(format!("{}.ico", name));
-                let icon_data = std::fs::read(&icon_path)?;
-                group_sizes.push(icon_data.len());
-                all_icons_data.extend_from_slice(&icon_data);
+    // Collect all icon data, frames in manifest order
+    for (icon_name, _, manifest) in packs {
+        let base = std::fs::canonicalize(Path::new("assets").join(icon_name))?;
+
+        for state in manifest.states.keys() {
+            for theme in &manifest.themes {
+                let mut group_sizes = Vec::new();
+                let group_offset = all_icons_data.len();
+
+                // Read all icons for this group
+                for file in manifest.frame_files(state, theme) {
+                    let icon_data = std::fs::read(base.join(&file))?;
+                    group_sizes.push(icon_data.len());
+                    all_icons_data.extend_from_slice(&icon_data);
+                }
+
+                metadata.push(IconGroupMetadata {
+                    icon_name: icon_name.to_string(),
+                    state: state.to_string(),
+                    theme: theme.to_string(),
+                    offset: group_offset,
+                    sizes: group_sizes,
+                });
             }
-
-            metadata.push(IconGroupMetadata {
-                icon_name: icon_name.to_string(),
-                theme: theme.to_string(),
-                offset: group_offset,
-                sizes: group_sizes,
-            });
         }
     }
 
@@ -125,7 +195,11 @@ fn generate_all_icons_compressed(
     Ok((compressed_path.display().to_string(), metadata))
 }
 
-fn generate_single_chunk_module(compressed_path: &str, metadata: &[IconGroupMetadata]) -> String {
+fn generate_single_chunk_module(
+    compressed_path: &str,
+    metadata: &[IconGroupMetadata],
+    packs: &[Pack],
+) -> String {
     let mut code = String::new();
 
     code.push_str("// All icons compressed into a single chunk for maximum compression\n");
@@ -139,7 +213,7 @@ fn generate_single_chunk_module(compressed_path: &str, metadata: &[IconGroupMeta
     // IconGroupInfo is now defined in main.rs
 
     // Generate size arrays for each group
-    for meta in metadata {
+    for (i, meta) in metadata.iter().enumerate() {
         let sizes_array = meta
             .sizes
             .iter()
@@ -148,11 +222,8 @@ fn generate_single_chunk_module(compressed_path: &str, metadata: &[IconGroupMeta
             .join(", ");
 
         code.push_str(&format!(
-            "const {}_{}_{}_SIZES: &[u32] = &[{}];\n",
-            meta.theme.to_uppercase(),
-            meta.icon_name.to_uppercase(),
-            "INDIVIDUAL",
-            sizes_array
+            "// {} {} {}\nconst GROUP_{}_SIZES: &[u32] = &[{}];\n",
+            meta.icon_name, meta.state, meta.theme, i, sizes_array
         ));
     }
 
@@ -160,40 +231,24 @@ fn generate_single_chunk_module(compressed_path: &str, metadata: &[IconGroupMeta
 
     // Generate function to get icon metadata
     code.push_str("pub fn get_icon_metadata() -> IconData {\n");
-    code.push_str("    let mut icons = HashMap::new();\n\n");
-
-    // Group metadata by icon name
-    let mut grouped_metadata: HashMap<String, Vec<&IconGroupMetadata>> = HashMap::new();
-    for meta in metadata {
-        grouped_metadata
-            .entry(meta.icon_name.clone())
-            .or_default()
-            .push(meta);
-    }
-
-    for (icon_name, themes) in grouped_metadata {
-        code.push_str(&format!("    // {} icons\n", icon_name));
-        code.push_str(&format!("    let mut {} = HashMap::new();\n", icon_name));
-
-        for meta in themes {
-            code.push_str(&format!(
-                "    {}.insert(\"{}\", IconGroupInfo {{ offset: {}, sizes: {}_{}_INDIVIDUAL_SIZES }});\n",
-                icon_name,
-                meta.theme,
-                meta.offset,
-                meta.theme.to_uppercase(),
-                meta.icon_name.to_uppercase()
-            ));
-        }
+    code.push_str("    let mut icons: IconData = HashMap::new();\n");
 
+    for (i, meta) in metadata.iter().enumerate() {
         code.push_str(&format!(
-            "    icons.insert(\"{}\", {});\n\n",
-            icon_name, icon_name
+            "    icons.entry({:?}).or_default().entry({:?}).or_default().insert({:?}, IconGroupInfo {{ offset: {}, sizes: GROUP_{}_SIZES }});\n",
+            meta.icon_name, meta.state, meta.theme, meta.offset, i
         ));
     }
 
     code.push_str("    icons\n");
-    code.push_str("}\n");
+    code.push_str("}\n\n");
+
+    // Manifests are parsed again at runtime for names, timing and tooltips
+    code.push_str("pub const MANIFESTS: &[(&str, &str)] = &[\n");
+    for (icon_name, text, _) in packs {
+        code.push_str(&format!("    ({:?}, {:?}),\n", icon_name, text));
+    }
+    code.push_str("];\n");
 
     code
 }
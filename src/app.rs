@@ -4,19 +4,26 @@ use std::sync::{Arc, Mutex};
 use std::thread;
 use std::time::Duration;
 
+use crate::badge::{Badge, BadgeCache, BADGE_CACHE_SIZE};
+use crate::cli;
 use crate::events::{build_menu, Events};
-use crate::icon_manager::{IconManager, Theme};
-use crate::platform::{CpuMonitor, SettingsManager, SystemIntegration};
-use crate::platform::{CpuMonitorImpl, SettingsManagerImpl, SystemIntegrationImpl};
+use crate::icon_frame::OwnedIcon;
+use crate::icon_manager::{AnimationState, IconManager, Theme};
+use crate::metrics::MetricRegistry;
+use crate::platform::{SettingsManager, SystemIntegration};
+use crate::platform::{SettingsManagerImpl, SystemIntegrationImpl};
+use crate::schedule::SleepOverride;
+use crate::smoothing::{Hysteresis, Smoother};
+use crate::speed_curve::SpeedCurve;
 use crate::debug;
 
 use trayicon::*;
 
-fn is_sleep_time() -> bool {
-    let hour = SystemIntegrationImpl::get_local_hour();
-    // Check if current hour is between 22:00 and 6:00
-    !(6..22).contains(&hour)
-}
+/// Smoothed usage at which packs with alert frames switch to them
+const ALERT_PERCENT: f64 = 90.0;
+
+/// Shown until the first sample is in
+const INITIAL_TOOLTIP: &str = "~Nyan~ RustCat - CPU Usage Monitor";
 
 // On macos, ui updates must be done on the main thread.
 // This is a workaround to ensure that UI updates are dispatched correctly.
@@ -33,101 +40,385 @@ fn ui_update<F: FnOnce() + Send + 'static>(f: F) {
 fn ui_update<F: FnOnce() + Send + 'static>(f: F) {
     f();
 }
+
+/// State a pack shows while sleeping or alert. Packs without frames for a
+/// state keep running instead.
+fn pick_state(
+    icon_manager: &IconManager,
+    icon_name: &str,
+    is_sleeping: bool,
+    is_alert: bool,
+) -> AnimationState {
+    if is_sleeping && icon_manager.has_state(icon_name, AnimationState::Sleeping) {
+        AnimationState::Sleeping
+    } else if is_alert && icon_manager.has_state(icon_name, AnimationState::Alert) {
+        AnimationState::Alert
+    } else {
+        AnimationState::Running
+    }
+}
+/// Options given on the command line for this run only. They win over the
+/// stored settings, also after those change on disk, until something else is
+/// picked from the menu.
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct Overrides {
+    pub icon: Option<String>,
+    pub theme: Option<Theme>,
+    pub metric: Option<String>,
+}
+
+/// Latest sample as the animation thread saw it
+#[derive(Debug, Clone, PartialEq)]
+pub struct Activity {
+    /// Smoothed value of the active metric, 0–100
+    pub usage: f64,
+    pub state: AnimationState,
+    pub tooltip: String,
+}
+
+/// A frame the animation thread just moved to
+#[derive(Debug, Clone, PartialEq)]
+pub struct FrameTick {
+    pub icon: String,
+    pub theme: Theme,
+    /// Position in the frames of `activity.state`
+    pub index: usize,
+    pub count: usize,
+    pub activity: Activity,
+}
+
+/// Read-only view of a running [`App`], for queries from outside the tray menu
+#[derive(Clone)]
+pub struct AppStatus {
+    icon_manager: Arc<IconManager>,
+    icon_name: Arc<Mutex<String>>,
+    theme: Arc<Mutex<Theme>>,
+    metrics: Arc<Mutex<MetricRegistry>>,
+    sleep_override: Arc<Mutex<SleepOverride>>,
+    activity: Arc<Mutex<Activity>>,
+}
+
+impl AppStatus {
+    pub fn icon_manager(&self) -> &IconManager {
+        &self.icon_manager
+    }
+
+    pub fn icon(&self) -> String {
+        self.icon_name.lock().unwrap().clone()
+    }
+
+    pub fn theme(&self) -> Theme {
+        *self.theme.lock().unwrap()
+    }
+
+    pub fn metric(&self) -> String {
+        self.metrics.lock().unwrap().active_name().to_string()
+    }
+
+    /// Names of the metrics that can be switched to
+    pub fn metric_names(&self) -> Vec<String> {
+        let metrics = self.metrics.lock().unwrap();
+        metrics.list().into_iter().map(|(name, _)| name).collect()
+    }
+
+    pub fn sleep_override(&self) -> SleepOverride {
+        *self.sleep_override.lock().unwrap()
+    }
+
+    pub fn activity(&self) -> Activity {
+        self.activity.lock().unwrap().clone()
+    }
+
+    #[cfg(test)]
+    pub fn set_activity(&self, activity: Activity) {
+        *self.activity.lock().unwrap() = activity;
+    }
+
+    /// The built-in packs showing the cat, without an [`App`] or settings
+    #[cfg(test)]
+    pub fn for_tests() -> Self {
+        AppStatus {
+            icon_manager: Arc::new(IconManager::load_icons().unwrap()),
+            icon_name: Arc::new(Mutex::new("cat".to_string())),
+            theme: Arc::new(Mutex::new(Theme::Dark)),
+            metrics: Arc::new(Mutex::new(MetricRegistry::with_platform_sources())),
+            sleep_override: Arc::new(Mutex::new(SleepOverride::default())),
+            activity: Arc::new(Mutex::new(Activity {
+                usage: 42.0,
+                state: AnimationState::Running,
+                tooltip: INITIAL_TOOLTIP.to_string(),
+            })),
+        }
+    }
+}
+
 pub struct App {
-    tray_icon: Arc<Mutex<TrayIcon<Events>>>,
+    /// `None` when started with `--no-tray`
+    tray_icon: Option<Arc<Mutex<TrayIcon<Events>>>>,
+    /// What the tray shows, kept until it is replaced since the tray's copy
+    /// borrows its data
+    shown_icon: Arc<Mutex<Option<OwnedIcon>>>,
     icon_manager: Arc<IconManager>,
     pub(crate) exit_flag: Arc<AtomicBool>,
     event_receiver: Option<mpsc::Receiver<Events>>,
+    event_sender: mpsc::Sender<Events>,
     icon_name: Arc<Mutex<String>>,
     theme: Arc<Mutex<Theme>>,
+    metrics: Arc<Mutex<MetricRegistry>>,
+    speed_curve: Arc<Mutex<SpeedCurve>>,
+    sleep_override: Arc<Mutex<SleepOverride>>,
+    badge: Arc<Mutex<Badge>>,
+    /// Set when the animation thread should re-read its sleep and smoothing settings
+    reload_config: Arc<AtomicBool>,
+    activity: Arc<Mutex<Activity>>,
+    /// Told about every frame, for outputs other than the tray icon
+    frame_listeners: Arc<Mutex<Vec<mpsc::Sender<FrameTick>>>>,
+    overrides: Overrides,
 }
 
 impl App {
+    /// `initial_icon` is the icon override, or the stored pack if it exists
     pub fn new(
         icon_manager: IconManager,
         initial_icon: &str,
-        initial_theme: Option<Theme>,
+        overrides: Overrides,
+        show_tray: bool,
     ) -> Result<Self, Box<dyn std::error::Error>> {
         let (sender, receiver) = mpsc::channel::<Events>();
         let icon_manager = Arc::new(icon_manager);
         let exit_flag = Arc::new(AtomicBool::new(false));
 
-        let theme = initial_theme.unwrap_or_else(SettingsManagerImpl::get_current_theme);
+        let theme = overrides
+            .theme
+            .unwrap_or_else(SettingsManagerImpl::get_current_theme);
+        let badge = SettingsManagerImpl::get_badge();
+
+        let mut metrics = MetricRegistry::with_platform_sources();
+        let metric = overrides
+            .metric
+            .clone()
+            .unwrap_or_else(SettingsManagerImpl::get_metric);
+        if !metrics.set_active(&metric) {
+            eprintln!(
+                "Unknown metric source '{}', falling back to {}",
+                metric,
+                metrics.active_name()
+            );
+        }
         let initial_icons = icon_manager
             .get_icon_set(initial_icon, Some(theme))
             .ok_or("Invalid initial icon name")?;
 
-        let tray_icon = TrayIconBuilder::new()
-            .sender(move |e: &Events| {
-                let _ = sender.send(e.clone());
-            })
-            .icon(initial_icons[0].clone())
-            .tooltip("~Nyan~ RustCat - CPU Usage Monitor")
-            .menu(build_menu(&icon_manager))
-            .on_right_click(Events::ShowMenu)
-            .on_double_click(Events::RunTaskmgr)
-            .build()?;
+        let event_sender = sender.clone();
+        let tray_icon = if show_tray {
+            let tray_icon = TrayIconBuilder::new()
+                .sender(move |e: &Events| {
+                    let _ = sender.send(e.clone());
+                })
+                .icon(initial_icons[0].icon().clone())
+                .tooltip(INITIAL_TOOLTIP)
+                .menu(build_menu(
+                    &icon_manager,
+                    initial_icon,
+                    theme,
+                    badge,
+                    &metrics,
+                    SleepOverride::default(),
+                ))
+                .on_right_click(Events::ShowMenu)
+                .on_double_click(Events::RunTaskmgr)
+                .build()?;
+            Some(Arc::new(Mutex::new(tray_icon)))
+        } else {
+            None
+        };
 
+        let shown_icon = tray_icon.as_ref().map(|_| initial_icons[0].clone());
         Ok(App {
-            tray_icon: Arc::new(Mutex::new(tray_icon)),
+            tray_icon,
+            shown_icon: Arc::new(Mutex::new(shown_icon)),
             icon_manager,
             exit_flag,
             event_receiver: Some(receiver),
+            event_sender,
             icon_name: Arc::new(Mutex::new(initial_icon.to_string())),
             theme: Arc::new(Mutex::new(theme)),
+            metrics: Arc::new(Mutex::new(metrics)),
+            speed_curve: Arc::new(Mutex::new(SettingsManagerImpl::get_speed_curve())),
+            sleep_override: Arc::new(Mutex::new(SleepOverride::default())),
+            badge: Arc::new(Mutex::new(badge)),
+            reload_config: Arc::new(AtomicBool::new(false)),
+            activity: Arc::new(Mutex::new(Activity {
+                usage: 0.0,
+                state: AnimationState::Running,
+                tooltip: INITIAL_TOOLTIP.to_string(),
+            })),
+            frame_listeners: Arc::new(Mutex::new(Vec::new())),
+            overrides,
         })
     }
 
+    /// Sender for events from outside the tray menu
+    pub fn event_sender(&self) -> mpsc::Sender<Events> {
+        self.event_sender.clone()
+    }
+
+    pub fn status(&self) -> AppStatus {
+        AppStatus {
+            icon_manager: self.icon_manager.clone(),
+            icon_name: self.icon_name.clone(),
+            theme: self.theme.clone(),
+            metrics: self.metrics.clone(),
+            sleep_override: self.sleep_override.clone(),
+            activity: self.activity.clone(),
+        }
+    }
+
+    /// Receive every frame the animation moves to, until the receiver is dropped
+    pub fn subscribe_frames(&self) -> mpsc::Receiver<FrameTick> {
+        let (sender, receiver) = mpsc::channel();
+        self.frame_listeners.lock().unwrap().push(sender);
+        receiver
+    }
+
     pub fn start_animation_thread(&self) {
         let exit_flag = self.exit_flag.clone();
         let tray_icon = self.tray_icon.clone();
+        let shown_icon = self.shown_icon.clone();
         let icon_manager = self.icon_manager.clone();
         let icon_name = self.icon_name.clone();
         let theme = self.theme.clone();
+        let metrics = self.metrics.clone();
+        let speed_curve = self.speed_curve.clone();
+        let sleep_override = self.sleep_override.clone();
+        let badge = self.badge.clone();
+        let reload_config = self.reload_config.clone();
+        let activity = self.activity.clone();
+        let frame_listeners = self.frame_listeners.clone();
 
         thread::spawn(move || {
             let sleep_interval = 10;
             let mut update_counter = 0;
             let mut animate_counter = 0;
             let mut icon_index = 0;
-            let mut speed = 200;
+            let mut speed = SpeedCurve::DEFAULT_MAX_DELAY;
             let mut idle_counter = 0;
-            let idle_threshold = 60 * 1000; // 1 minute in milliseconds
+            let mut sleep_config = SettingsManagerImpl::get_sleep_config();
+            let mut idle_threshold = sleep_config.idle_seconds * 1000;
+            // What idle detection alone decided, before any menu override
+            let mut auto_sleeping = false;
             let mut is_sleeping = false;
+            let mut smoothing = SettingsManagerImpl::get_smoothing_config();
+            let mut smoother = Smoother::new(smoothing);
+            let mut idle_smoother = Smoother::new(smoothing);
+            // Idle below the cutoff, awake again only once usage clears the band above it
+            let mut idle_band = Hysteresis::new(sleep_config.idle_percent, smoothing.hysteresis);
+            let mut busy_band = Hysteresis::new(ALERT_PERCENT, smoothing.hysteresis);
+            let mut is_alert = false;
+            let mut current_state = AnimationState::Running;
+            let mut badged_icons = BadgeCache::new(BADGE_CACHE_SIZE);
 
             while !exit_flag.load(Ordering::Relaxed) {
                 thread::sleep(Duration::from_millis(sleep_interval));
 
+                if reload_config.swap(false, Ordering::Relaxed) {
+                    sleep_config = SettingsManagerImpl::get_sleep_config();
+                    idle_threshold = sleep_config.idle_seconds * 1000;
+                    smoothing = SettingsManagerImpl::get_smoothing_config();
+                    smoother = Smoother::new(smoothing);
+                    idle_smoother = Smoother::new(smoothing);
+                    idle_band = Hysteresis::new(sleep_config.idle_percent, smoothing.hysteresis);
+                    busy_band = Hysteresis::new(ALERT_PERCENT, smoothing.hysteresis);
+                }
+
                 let current_icon_name = icon_name.lock().unwrap().clone();
                 let current_theme = *theme.lock().unwrap();
                 
-                // Determine which icon set to use based on idle state
-                let icon_set_name = if is_sleeping && current_icon_name == "cat" {
-                    "sleep"
-                } else {
-                    &current_icon_name
-                };
-                
-                let icons = match icon_manager.get_icon_set(icon_set_name, Some(current_theme))
-                {
+                let state = pick_state(&icon_manager, &current_icon_name, is_sleeping, is_alert);
+                if state != current_state {
+                    current_state = state;
+                    icon_index = 0; // Restart the animation from its first frame
+                    activity.lock().unwrap().state = state;
+                }
+
+                let icons = match icon_manager.get_state_icon_set(
+                    &current_icon_name,
+                    state,
+                    Some(current_theme),
+                ) {
                     Some(icons) => icons,
                     None => {
-                        eprintln!("Invalid icon name: {}", icon_set_name);
+                        eprintln!("Invalid icon name: {} ({})", current_icon_name, state);
                         continue;
                     }
                 };
 
-                if animate_counter >= speed {
+                // Packs can hold some frames longer than others
+                let duration = icon_manager.frame_duration(
+                    &current_icon_name,
+                    state,
+                    Some(current_theme),
+                    icon_index,
+                );
+                let frame_delay = (speed as f64 * duration).round() as u64;
+                if animate_counter >= frame_delay {
                     animate_counter = 0;
                     icon_index += 1;
                     icon_index %= icons.len();
-                    {
+                    let mut listeners = frame_listeners.lock().unwrap();
+                    if !listeners.is_empty() {
+                        let tick = FrameTick {
+                            icon: current_icon_name.clone(),
+                            theme: current_theme,
+                            index: icon_index,
+                            count: icons.len(),
+                            activity: activity.lock().unwrap().clone(),
+                        };
+                        listeners.retain(|listener| listener.send(tick.clone()).is_ok());
+                    }
+                    drop(listeners);
+                    if let Some(tray_icon) = &tray_icon {
                         let tray_icon_clone = tray_icon.clone();
-                        let icon_data = icons[icon_index].clone();
+                        let current_badge = *badge.lock().unwrap();
+                        let icon_data = if current_badge == Badge::Off {
+                            icons[icon_index].clone()
+                        } else {
+                            let resolved = current_theme.resolve();
+                            let usage = activity.lock().unwrap().usage;
+                            let value = usage.round().clamp(0.0, 100.0) as u8;
+                            let key = (
+                                current_icon_name.clone(),
+                                resolved,
+                                current_badge,
+                                value,
+                                state,
+                                icon_index,
+                            );
+                            badged_icons
+                                .get_or_draw(key, || {
+                                    icon_manager
+                                        .get_state_frames(
+                                            &current_icon_name,
+                                            state,
+                                            Some(current_theme),
+                                        )
+                                        .and_then(|frames| frames.get(icon_index))
+                                        .ok_or_else(|| "frame not found".to_string())
+                                        .and_then(|frame| {
+                                            current_badge.draw(frame, value, resolved).to_icon()
+                                        })
+                                })
+                                .unwrap_or_else(|e| {
+                                    eprintln!("Failed to draw usage badge: {}", e);
+                                    icons[icon_index].clone()
+                                })
+                        };
+                        let shown_icon = shown_icon.clone();
                         ui_update(move || {
                             if let Ok(mut tray) = tray_icon_clone.lock() {
-                                if let Err(e) = tray.set_icon(&icon_data) {
-                                    eprintln!("set_icon error: {:?}", e);
+                                match tray.set_icon(icon_data.icon()) {
+                                    Ok(()) => *shown_icon.lock().unwrap() = Some(icon_data),
+                                    Err(e) => eprintln!("set_icon error: {:?}", e),
                                 }
                             }
                         });
@@ -137,44 +428,68 @@ impl App {
 
                 if update_counter >= 1000 {
                     update_counter = 0;
-                    let usage = match CpuMonitorImpl::get_cpu_usage() {
-                        Ok(usage) => usage,
+                    let sample = {
+                        let mut metrics = metrics.lock().unwrap();
+                        metrics.sample().and_then(|(reading, description)| {
+                            let idle_value = metrics.idle_value(&reading)?;
+                            Ok((reading, description, idle_value))
+                        })
+                    };
+                    let (reading, description, idle_value) = match sample {
+                        Ok(sample) => sample,
                         Err(e) => {
-                            eprintln!("Failed to get CPU usage: {}", e);
+                            eprintln!("Failed to sample metric: {}", e);
                             continue;
                         }
                     };
-                    speed = (200.0 / (usage / 5.0).clamp(1.0_f64, 20.0_f64)).round() as u64;
-                    debug!("CPU Usage: {:.2}% speed: {}", usage, speed);
+                    // Speed and sleep follow the smoothed value; the tooltip
+                    // keeps describing the raw sample.
+                    let usage = smoother.update(reading.value, 1.0);
+                    speed = speed_curve.lock().unwrap().frame_delay(usage);
+                    debug!("{} smoothed: {:.2}% speed: {}", description, usage, speed);
 
-                    // Check if CPU is idle (less than 5% usage) and it's sleep time (22:00-6:00)
-                    if usage < 5.0 && is_sleep_time() {
+                    // Fall asleep once the CPU has been idle long enough inside
+                    // a scheduled sleep window, whichever metric sets the speed
+                    let is_idle = idle_band.update(idle_smoother.update(idle_value, 1.0));
+                    let local_time = SystemIntegrationImpl::get_local_time();
+                    if is_idle && sleep_config.schedule.allows(local_time) {
                         idle_counter += 1000; // Add the update interval
-                        if idle_counter >= idle_threshold && !is_sleeping {
-                            is_sleeping = true;
-                            icon_index = 0; // Reset animation to start from first sleeping frame
-                            debug!("CPU has been idle for 1 minutes during sleep hours, switching to sleeping cat");
+                        if idle_counter >= idle_threshold && !auto_sleeping {
+                            auto_sleeping = true;
+                            debug!(
+                                "Idle for {} seconds during sleep hours, switching to sleeping cat",
+                                sleep_config.idle_seconds
+                            );
                         }
                     } else {
                         idle_counter = 0;
-                        if is_sleeping {
-                            is_sleeping = false;
-                            icon_index = 0; // Reset animation
-                            if usage >= 5.0 {
-                                debug!("CPU activity detected, switching back to normal cat");
+                        if auto_sleeping {
+                            auto_sleeping = false;
+                            if !is_idle {
+                                debug!("Activity detected, switching back to normal cat");
                             } else {
                                 debug!("Outside sleep hours, switching back to normal cat");
                             }
                         }
                     }
 
+                    is_sleeping = sleep_override.lock().unwrap().resolve(auto_sleeping);
+                    is_alert = !busy_band.update(usage);
+
+                    // For the state the next frame switches to
+                    let state =
+                        pick_state(&icon_manager, &current_icon_name, is_sleeping, is_alert);
+                    let tooltip = match icon_manager.state_tooltip(&current_icon_name, state) {
+                        Some(text) if is_sleeping => text.to_string(),
+                        _ => description,
+                    };
                     {
+                        let mut activity = activity.lock().unwrap();
+                        activity.usage = usage;
+                        activity.tooltip = tooltip.clone();
+                    }
+                    if let Some(tray_icon) = &tray_icon {
                         let tray_icon_clone = tray_icon.clone();
-                        let tooltip = if is_sleeping && current_icon_name == "cat" {
-                            "Shhhh, Your CPU is sleeping...💤".to_string()
-                        } else {
-                            format!("CPU Usage: {:.2}%", usage)
-                        };
                         ui_update(move || {
                             if let Ok(mut tray) = tray_icon_clone.lock() {
                                 if let Err(e) = tray.set_tooltip(&tooltip) {
@@ -215,27 +530,49 @@ impl App {
                     }
                     Events::SetTheme(theme) => {
                         SettingsManagerImpl::set_current_theme(Some(theme));
+                        self.overrides.theme = None;
                         *self.theme.lock().unwrap() = theme;
                         self.update_menu();
                     }
+                    Events::SetBadge(badge) => {
+                        SettingsManagerImpl::set_badge(badge);
+                        *self.badge.lock().unwrap() = badge;
+                        self.update_menu();
+                    }
                     Events::SetIcon(icon_name) => {
                         SettingsManagerImpl::set_current_icon(&icon_name);
+                        self.overrides.icon = None;
                         *self.icon_name.lock().unwrap() = icon_name;
                         self.update_menu();
                     }
+                    Events::SetMetric(name) => {
+                        if self.metrics.lock().unwrap().set_active(&name) {
+                            SettingsManagerImpl::set_metric(&name);
+                            self.overrides.metric = None;
+                            self.update_menu();
+                        }
+                    }
+                    Events::SetSpeedCurve(kind) => {
+                        SettingsManagerImpl::set_speed_curve_kind(kind);
+                        *self.speed_curve.lock().unwrap() = SettingsManagerImpl::get_speed_curve();
+                        self.update_menu();
+                    }
+                    Events::SetSleepOverride(mode) => {
+                        *self.sleep_override.lock().unwrap() = mode;
+                        self.update_menu();
+                    }
+                    Events::SettingsChanged => {
+                        self.apply_stored_settings();
+                        self.update_menu();
+                    }
                     Events::ToggleRunOnStart => {
                         let current_state = SettingsManagerImpl::is_run_on_start_enabled();
                         SettingsManagerImpl::set_run_on_start(!current_state);
                         self.update_menu();
                     }
                     Events::ShowAboutDialog => {
-                        let version = env!("CARGO_PKG_VERSION");
-                        let git_hash = option_env!("GIT_HASH").unwrap_or("N/A");
                         let project_page = "https://github.com/bearice/RustCat";
-                        let message = format!(
-                            "RustCat version {} (Git: {})\nProject Page: {}",
-                            version, git_hash, project_page
-                        );
+                        let message = format!("{}\nProject Page: {}", cli::version(), project_page);
 
                         if let Err(e) =
                             SystemIntegrationImpl::show_dialog(&message, "About RustCat")
@@ -244,7 +581,10 @@ impl App {
                         }
                     }
                     Events::ShowMenu => {
-                        if let Ok(mut tray) = self.tray_icon.lock() {
+                        let Some(tray_icon) = &self.tray_icon else {
+                            continue;
+                        };
+                        if let Ok(mut tray) = tray_icon.lock() {
                             if let Err(e) = tray.show_menu() {
                                 eprintln!("Failed to show menu: {}", e);
                             }
@@ -255,12 +595,62 @@ impl App {
         }
     }
 
+    /// Pick up settings changed outside the menu, except those overridden
+    /// on the command line
+    fn apply_stored_settings(&self) {
+        if self.overrides.icon.is_none() {
+            let icon_name = SettingsManagerImpl::get_current_icon();
+            if self.icon_manager.available_icons().contains(&icon_name) {
+                *self.icon_name.lock().unwrap() = icon_name;
+            } else {
+                eprintln!(
+                    "Icon pack '{}' not found, keeping the current one",
+                    icon_name
+                );
+            }
+        }
+        if self.overrides.theme.is_none() {
+            *self.theme.lock().unwrap() = SettingsManagerImpl::get_current_theme();
+        }
+        *self.badge.lock().unwrap() = SettingsManagerImpl::get_badge();
+
+        if self.overrides.metric.is_none() {
+            let metric = SettingsManagerImpl::get_metric();
+            let mut metrics = self.metrics.lock().unwrap();
+            if metrics.active_name() != metric && !metrics.set_active(&metric) {
+                eprintln!(
+                    "Unknown metric source '{}', keeping {}",
+                    metric,
+                    metrics.active_name()
+                );
+            }
+        }
+
+        *self.speed_curve.lock().unwrap() = SettingsManagerImpl::get_speed_curve();
+        self.reload_config.store(true, Ordering::Relaxed);
+    }
+
     fn update_menu(&self) {
-        let tray_icon = self.tray_icon.clone();
+        let Some(tray_icon) = self.tray_icon.clone() else {
+            return;
+        };
         let icon_manager = self.icon_manager.clone();
+        let icon_name = self.icon_name.lock().unwrap().clone();
+        let theme = *self.theme.lock().unwrap();
+        let badge = *self.badge.lock().unwrap();
+        let metrics = self.metrics.clone();
+        let sleep_override = *self.sleep_override.lock().unwrap();
         ui_update(move || {
             if let Ok(mut tray) = tray_icon.lock() {
-                if let Err(e) = tray.set_menu(&build_menu(&icon_manager)) {
+                let menu = build_menu(
+                    &icon_manager,
+                    &icon_name,
+                    theme,
+                    badge,
+                    &metrics.lock().unwrap(),
+                    sleep_override,
+                );
+                if let Err(e) = tray.set_menu(&menu) {
                     eprintln!("Failed to update menu: {}", e);
                 }
             }
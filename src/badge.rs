@@ -0,0 +1,284 @@
+//! Usage percentage drawn onto the tray icon.
+//!
+//! The number sits on a small plate in one corner of every frame, so the
+//! reading is visible without hovering for the tooltip. Digits come from a
+//! built-in 3×5 bitmap font, scaled up by whole pixels for larger entries.
+
+use crate::icon_frame::{IconFrame, RgbaImage};
+use crate::icon_manager::{AnimationState, Theme};
+use std::collections::HashMap;
+
+/// Badged tray icons kept at a time: a few values for every frame of a
+/// state, so a steady reading isn't drawn again on every loop
+pub const BADGE_CACHE_SIZE: usize = 128;
+
+/// Corner the badge is drawn in, or none
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Badge {
+    #[default]
+    Off,
+    TopLeft,
+    TopRight,
+    BottomLeft,
+    BottomRight,
+}
+
+impl Badge {
+    pub const ALL: [Badge; 5] = [
+        Badge::Off,
+        Badge::TopLeft,
+        Badge::TopRight,
+        Badge::BottomLeft,
+        Badge::BottomRight,
+    ];
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "off" => Some(Badge::Off),
+            "top-left" => Some(Badge::TopLeft),
+            "top-right" => Some(Badge::TopRight),
+            "bottom-left" => Some(Badge::BottomLeft),
+            "bottom-right" => Some(Badge::BottomRight),
+            _ => None,
+        }
+    }
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            Badge::Off => "Off",
+            Badge::TopLeft => "Top Left",
+            Badge::TopRight => "Top Right",
+            Badge::BottomLeft => "Bottom Left",
+            Badge::BottomRight => "Bottom Right",
+        }
+    }
+
+    /// `frame` with `value` drawn on every size, for a resolved `theme`.
+    /// Plates take the panel's color and digits the icon's, so the number
+    /// stands out from both.
+    pub fn draw(self, frame: &IconFrame, value: u8, theme: Theme) -> IconFrame {
+        if self == Badge::Off {
+            return frame.clone();
+        }
+        let (plate, ink) = match theme {
+            // Light icons for dark panels
+            Theme::Dark => ([24, 24, 24, 255], [255, 255, 255, 255]),
+            Theme::Light => ([255, 255, 255, 255], [16, 16, 16, 255]),
+            // Only macOS keeps Auto after Theme::resolve. Its template icons
+            // only keep the alpha channel, so the digits are cut out instead
+            #[cfg(any(target_os = "macos", target_os = "linux"))]
+            Theme::Auto => ([0, 0, 0, 255], [0, 0, 0, 0]),
+        };
+        frame.map_images(|image| self.draw_image(image, &value.to_string(), plate, ink))
+    }
+
+    fn draw_image(self, image: &RgbaImage, text: &str, plate: [u8; 4], ink: [u8; 4]) -> RgbaImage {
+        let mut image = image.clone();
+        let scale = (image.width / 32).max(1);
+        // One pixel between digits and around them
+        let width = ((text.len() as u32) * (GLYPH_WIDTH + 1) + 1) * scale;
+        let height = (GLYPH_HEIGHT + 2) * scale;
+        if width > image.width || height > image.height {
+            return image;
+        }
+        let left = match self {
+            Badge::TopLeft | Badge::BottomLeft => 0,
+            _ => image.width - width,
+        };
+        let top = match self {
+            Badge::TopLeft | Badge::TopRight => 0,
+            _ => image.height - height,
+        };
+
+        for y in 0..height {
+            for x in 0..width {
+                image.set_pixel(left + x, top + y, plate);
+            }
+        }
+        for (i, digit) in text.bytes().enumerate() {
+            let glyph = DIGITS[(digit - b'0') as usize];
+            let glyph_left = left + (1 + i as u32 * (GLYPH_WIDTH + 1)) * scale;
+            for row in 0..GLYPH_HEIGHT {
+                for col in 0..GLYPH_WIDTH {
+                    if glyph[row as usize] & (0b100 >> col) == 0 {
+                        continue;
+                    }
+                    for dy in 0..scale {
+                        for dx in 0..scale {
+                            let x = glyph_left + col * scale + dx;
+                            let y = top + (1 + row) * scale + dy;
+                            image.set_pixel(x, y, ink);
+                        }
+                    }
+                }
+            }
+        }
+        image
+    }
+}
+
+impl std::fmt::Display for Badge {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Badge::Off => write!(f, "off"),
+            Badge::TopLeft => write!(f, "top-left"),
+            Badge::TopRight => write!(f, "top-right"),
+            Badge::BottomLeft => write!(f, "bottom-left"),
+            Badge::BottomRight => write!(f, "bottom-right"),
+        }
+    }
+}
+
+/// Pack, resolved theme, corner, value, state and frame of a badged icon
+pub type BadgeKey = (String, Theme, Badge, u8, AnimationState, usize);
+
+/// Badged tray icons, each drawn the first time its value shows on a frame
+/// and kept while it is among the most recently used. The oldest is dropped
+/// once there are `capacity` of them.
+pub struct BadgeCache<I> {
+    icons: HashMap<BadgeKey, (I, u64)>,
+    capacity: usize,
+    /// Counts every lookup, to tell which icon was used least recently
+    clock: u64,
+}
+
+impl<I: Clone> BadgeCache<I> {
+    pub fn new(capacity: usize) -> Self {
+        BadgeCache {
+            icons: HashMap::new(),
+            capacity,
+            clock: 0,
+        }
+    }
+
+    /// The icon for `key`, drawn with `draw` if it isn't kept already
+    pub fn get_or_draw(
+        &mut self,
+        key: BadgeKey,
+        draw: impl FnOnce() -> Result<I, String>,
+    ) -> Result<I, String> {
+        self.clock += 1;
+        if let Some((icon, used)) = self.icons.get_mut(&key) {
+            *used = self.clock;
+            return Ok(icon.clone());
+        }
+        let icon = draw()?;
+        if self.icons.len() >= self.capacity {
+            let oldest = self
+                .icons
+                .iter()
+                .min_by_key(|(_, (_, used))| *used)
+                .map(|(key, _)| key.clone());
+            if let Some(oldest) = oldest {
+                self.icons.remove(&oldest);
+            }
+        }
+        self.icons.insert(key, (icon.clone(), self.clock));
+        Ok(icon)
+    }
+}
+
+const GLYPH_WIDTH: u32 = 3;
+const GLYPH_HEIGHT: u32 = 5;
+
+/// Rows of 0–9, top to bottom, the leftmost pixel in the highest bit
+const DIGITS: [[u8; 5]; 10] = [
+    [0b111, 0b101, 0b101, 0b101, 0b111],
+    [0b010, 0b110, 0b010, 0b010, 0b111],
+    [0b111, 0b001, 0b111, 0b100, 0b111],
+    [0b111, 0b001, 0b111, 0b001, 0b111],
+    [0b101, 0b101, 0b111, 0b001, 0b001],
+    [0b111, 0b100, 0b111, 0b001, 0b111],
+    [0b111, 0b100, 0b111, 0b101, 0b111],
+    [0b111, 0b001, 0b001, 0b001, 0b001],
+    [0b111, 0b101, 0b111, 0b101, 0b111],
+    [0b111, 0b101, 0b111, 0b001, 0b111],
+];
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// The badge area of `image` as text, `#` for ink and `.` for plate
+    fn ascii(image: &RgbaImage, left: u32, top: u32, width: u32, height: u32) -> Vec<String> {
+        (top..top + height)
+            .map(|y| {
+                (left..left + width)
+                    .map(|x| match image.pixel(x, y) {
+                        [255, 255, 255, 255] => '#',
+                        [24, 24, 24, 255] => '.',
+                        _ => '?',
+                    })
+                    .collect()
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_draw() {
+        let cat = std::fs::read("assets/cat/dark_cat_0.ico").unwrap();
+        let frame = IconFrame::from_ico(&cat).unwrap();
+        let badged = Badge::BottomRight.draw(&frame, 73, Theme::Dark);
+        let image = badged.largest();
+        assert_eq!(
+            ascii(image, 32 - 9, 32 - 7, 9, 7),
+            [
+                ".........",
+                ".###.###.",
+                "...#...#.",
+                "...#.###.",
+                "...#...#.",
+                "...#.###.",
+                ".........",
+            ]
+        );
+        // The rest of the frame is untouched
+        assert_eq!(image.pixel(0, 0), frame.largest().pixel(0, 0));
+
+        let badged = Badge::TopLeft.draw(&frame, 100, Theme::Dark);
+        assert_eq!(
+            ascii(badged.largest(), 0, 0, 13, 2),
+            [".............", "..#..###.###."]
+        );
+        assert_eq!(Badge::Off.draw(&frame, 100, Theme::Dark), frame);
+    }
+
+    #[test]
+    fn test_cache_draws_each_icon_once() {
+        let key = |value| {
+            let state = AnimationState::Running;
+            (
+                "cat".to_string(),
+                Theme::Dark,
+                Badge::BottomRight,
+                value,
+                state,
+                0,
+            )
+        };
+        let mut cache = BadgeCache::new(2);
+        let mut drawn = Vec::new();
+        let mut get = |cache: &mut BadgeCache<u8>, value| {
+            cache
+                .get_or_draw(key(value), || {
+                    drawn.push(value);
+                    Ok(value)
+                })
+                .unwrap()
+        };
+
+        assert_eq!(get(&mut cache, 42), 42);
+        // Only drawn again once the value changes
+        get(&mut cache, 42);
+        get(&mut cache, 43);
+        get(&mut cache, 42);
+        // 43 was used least recently, so it makes room for 44
+        get(&mut cache, 44);
+        get(&mut cache, 42);
+        get(&mut cache, 43);
+        assert_eq!(drawn, [42, 43, 44, 43]);
+
+        let failed = cache.get_or_draw(key(99), || Err("no frame".to_string()));
+        assert_eq!(failed, Err("no frame".to_string()));
+    }
+}
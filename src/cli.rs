@@ -0,0 +1,284 @@
+//! Command-line arguments.
+//!
+//! Parsed by hand: the handful of flags doesn't justify an argument parsing
+//! dependency in a binary built for size. Overrides given here apply to this
+//! run only and are never written to the settings store; picking something
+//! from the tray menu still saves it as usual.
+
+use crate::app::Overrides;
+use crate::icon_manager::{AnimationState, IconManager, Theme};
+use crate::platform::{SettingsManager, SettingsManagerImpl};
+use crate::stream::{Format, Glyphs};
+use crate::tui::Renderer;
+#[cfg(target_os = "linux")]
+use std::path::PathBuf;
+
+pub const USAGE: &str = "\
+Usage: rust_cat [OPTIONS] [COMMAND]
+
+Commands:
+  status          Print the settings this run would use
+  list-icons      List the available icon packs
+  reset-settings  Restore the default settings
+  stream          Write the animation to stdout for a status bar
+  tui             Draw the animation in the terminal
+
+Options:
+  --icon <NAME>    Icon pack for this run, see list-icons
+  --theme <THEME>  dark, light or auto (macOS and Linux)
+  --metric <NAME>  What drives the animation: cpu, cpu-busiest or memory
+  --config <PATH>  Settings file to use instead of the default (Linux)
+  --no-tray        Run without a tray icon
+  --format <FMT>   stream output: plain, waybar or i3bar
+  --glyphs <SET>   stream glyphs: unicode, or nerd for a Nerd Font
+  --renderer <R>   tui graphics: kitty, sixel or blocks (default: detected)
+  --debug          Log every sample, even when not run from a terminal
+  -V, --version    Print the version
+  -h, --help       Print this help
+";
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum Command {
+    /// Start the tray app
+    #[default]
+    Run,
+    Status,
+    ListIcons,
+    ResetSettings,
+    /// Write frames to stdout instead of showing a tray icon
+    Stream,
+    /// Draw frames in the terminal instead of showing a tray icon
+    Tui,
+    Version,
+    Help,
+}
+
+#[derive(Debug, Default, PartialEq)]
+pub struct Options {
+    pub command: Command,
+    pub icon: Option<String>,
+    pub theme: Option<Theme>,
+    pub metric: Option<String>,
+    #[cfg(target_os = "linux")]
+    pub config: Option<PathBuf>,
+    pub no_tray: bool,
+    pub format: Format,
+    pub glyphs: Glyphs,
+    /// `None` picks one for the terminal
+    pub renderer: Option<Renderer>,
+    pub debug: bool,
+}
+
+impl Options {
+    /// The settings this run overrides
+    pub fn overrides(&self) -> Overrides {
+        Overrides {
+            icon: self.icon.clone(),
+            theme: self.theme,
+            metric: self.metric.clone(),
+        }
+    }
+}
+
+/// Parse the arguments after the program name.
+pub fn parse(args: impl IntoIterator<Item = String>) -> Result<Options, String> {
+    let mut options = Options::default();
+    let mut command = None;
+    let mut args = args.into_iter();
+    while let Some(arg) = args.next() {
+        // Accept both `--icon parrot` and `--icon=parrot`
+        let (flag, inline_value) = match arg.split_once('=') {
+            Some((flag, value)) if flag.starts_with("--") => (flag, Some(value.to_string())),
+            _ => (arg.as_str(), None),
+        };
+        let mut value = || {
+            inline_value
+                .clone()
+                .or_else(|| args.next())
+                .ok_or(format!("{} needs a value", flag))
+        };
+        match flag {
+            "--icon" => options.icon = Some(value()?),
+            "--theme" => {
+                let name = value()?;
+                options.theme =
+                    Some(Theme::from_name(&name).ok_or(format!("unknown theme '{}'", name))?);
+            }
+            "--metric" => options.metric = Some(value()?),
+            #[cfg(target_os = "linux")]
+            "--config" => options.config = Some(PathBuf::from(value()?)),
+            #[cfg(not(target_os = "linux"))]
+            "--config" => return Err("--config is only supported on Linux".to_string()),
+            "--no-tray" => options.no_tray = true,
+            "--format" => {
+                let name = value()?;
+                options.format =
+                    Format::from_name(&name).ok_or(format!("unknown format '{}'", name))?;
+            }
+            "--glyphs" => {
+                let name = value()?;
+                options.glyphs =
+                    Glyphs::from_name(&name).ok_or(format!("unknown glyph set '{}'", name))?;
+            }
+            "--renderer" => {
+                let name = value()?;
+                options.renderer =
+                    Some(Renderer::from_name(&name).ok_or(format!("unknown renderer '{}'", name))?);
+            }
+            "--debug" => options.debug = true,
+            "-V" | "--version" => command = Some(Command::Version),
+            "-h" | "--help" => command = Some(Command::Help),
+            _ if flag.starts_with('-') => return Err(format!("unknown option '{}'", flag)),
+            _ => {
+                let next = match flag {
+                    "status" => Command::Status,
+                    "list-icons" => Command::ListIcons,
+                    "reset-settings" => Command::ResetSettings,
+                    "stream" => Command::Stream,
+                    "tui" => Command::Tui,
+                    _ => return Err(format!("unknown command '{}'", flag)),
+                };
+                match command {
+                    None => command = Some(next),
+                    // --help and --version win over any command
+                    Some(Command::Help | Command::Version) => {}
+                    Some(_) => return Err("only one command can be given".to_string()),
+                }
+            }
+        }
+    }
+    options.command = command.unwrap_or_default();
+    Ok(options)
+}
+
+/// Version line shared by `--version` and the About dialog
+pub fn version() -> String {
+    format!(
+        "RustCat version {} (Git: {})",
+        env!("CARGO_PKG_VERSION"),
+        option_env!("GIT_HASH").unwrap_or("N/A")
+    )
+}
+
+/// `list-icons`: one pack per line, with the themes and extra states it has
+pub fn list_icons(icon_manager: &IconManager, current: &str) {
+    for name in icon_manager.available_icons() {
+        let themes: Vec<String> = icon_manager
+            .available_themes_for_icon(&name)
+            .iter()
+            .map(Theme::to_string)
+            .collect();
+        let states: Vec<&str> = [AnimationState::Sleeping, AnimationState::Alert]
+            .into_iter()
+            .filter(|state| icon_manager.has_state(&name, *state))
+            .map(|state| state.name())
+            .collect();
+        println!(
+            "{} {:<12} {:<20} themes: {}{}",
+            if name == current { '*' } else { ' ' },
+            name,
+            icon_manager.display_name(&name),
+            themes.join(", "),
+            if states.is_empty() {
+                String::new()
+            } else {
+                format!("; states: {}", states.join(", "))
+            }
+        );
+    }
+}
+
+/// `status`: the settings this run would use, overrides included
+pub fn print_status(icon: &str, theme: Theme, metric: &str) {
+    let curve = SettingsManagerImpl::get_speed_curve();
+    let smoothing = SettingsManagerImpl::get_smoothing_config();
+    let sleep = SettingsManagerImpl::get_sleep_config();
+    println!("{}", version());
+    println!("Settings:      {}", SettingsManagerImpl::location());
+    println!("Icon:          {}", icon);
+    println!("Theme:         {}", theme);
+    println!("Metric:        {}", metric);
+    println!("Usage badge:   {}", SettingsManagerImpl::get_badge());
+    println!(
+        "Speed curve:   {} ({}-{} ms per frame)",
+        curve.kind, curve.min_delay, curve.max_delay
+    );
+    println!(
+        "Smoothing:     {} s, median of {}, hysteresis {}",
+        smoothing.time_constant, smoothing.median_window, smoothing.hysteresis
+    );
+    println!(
+        "Sleep:         {} after {} s below {}%",
+        sleep.schedule, sleep.idle_seconds, sleep.idle_percent
+    );
+    println!(
+        "Run on start:  {}",
+        if SettingsManagerImpl::is_run_on_start_enabled() {
+            "yes"
+        } else {
+            "no"
+        }
+    );
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse_args(args: &str) -> Result<Options, String> {
+        parse(args.split_whitespace().map(str::to_string))
+    }
+
+    #[test]
+    fn test_parse() {
+        assert_eq!(parse_args("").unwrap(), Options::default());
+
+        let options = parse_args("--icon parrot --theme=light --metric memory --no-tray").unwrap();
+        assert_eq!(options.command, Command::Run);
+        assert_eq!(options.icon.as_deref(), Some("parrot"));
+        assert_eq!(options.theme, Some(Theme::Light));
+        assert_eq!(options.metric.as_deref(), Some("memory"));
+        assert!(options.no_tray && !options.debug);
+
+        // Options may come before or after the command
+        let options = parse_args("--debug status --icon=cat").unwrap();
+        assert_eq!(options.command, Command::Status);
+        assert_eq!(options.icon.as_deref(), Some("cat"));
+        assert!(options.debug);
+
+        assert_eq!(
+            parse_args("list-icons --help").unwrap().command,
+            Command::Help
+        );
+        assert_eq!(parse_args("--help status").unwrap().command, Command::Help);
+        assert_eq!(parse_args("-V").unwrap().command, Command::Version);
+
+        let options = parse_args("stream --format waybar --glyphs=nerd").unwrap();
+        assert_eq!(options.command, Command::Stream);
+        assert_eq!(options.format, Format::Waybar);
+        assert_eq!(options.glyphs, Glyphs::Nerd);
+
+        let options = parse_args("tui --renderer sixel").unwrap();
+        assert_eq!(options.command, Command::Tui);
+        assert_eq!(options.renderer, Some(Renderer::Sixel));
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        assert_eq!(parse_args("--icon").unwrap_err(), "--icon needs a value");
+        assert_eq!(
+            parse_args("--theme purple").unwrap_err(),
+            "unknown theme 'purple'"
+        );
+        assert_eq!(parse_args("--fast").unwrap_err(), "unknown option '--fast'");
+        assert_eq!(parse_args("feed").unwrap_err(), "unknown command 'feed'");
+        assert_eq!(
+            parse_args("stream --format json").unwrap_err(),
+            "unknown format 'json'"
+        );
+        assert_eq!(
+            parse_args("status list-icons").unwrap_err(),
+            "only one command can be given"
+        );
+    }
+}
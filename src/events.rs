@@ -1,5 +1,9 @@
+use crate::badge::Badge;
 use crate::icon_manager::{IconManager, Theme};
+use crate::metrics::MetricRegistry;
 use crate::platform::{SettingsManager, SettingsManagerImpl};
+use crate::schedule::SleepOverride;
+use crate::speed_curve::CurveKind;
 use crate::debug;
 use trayicon::MenuBuilder;
 
@@ -7,30 +11,43 @@ use trayicon::MenuBuilder;
 pub enum Events {
     Exit,
     SetTheme(Theme),
+    SetBadge(Badge),
     SetIcon(String),
+    SetMetric(String),
+    SetSpeedCurve(CurveKind),
+    SetSleepOverride(SleepOverride),
+    /// The stored settings were changed outside the menu
+    SettingsChanged,
     RunTaskmgr,
     ToggleRunOnStart,
     ShowAboutDialog,
     ShowMenu,
 }
 
-pub fn build_menu(icon_manager: &IconManager) -> MenuBuilder<Events> {
+/// The tray menu for what the app currently shows, command-line overrides
+/// included
+pub fn build_menu(
+    icon_manager: &IconManager,
+    current_icon: &str,
+    current_theme: Theme,
+    current_badge: Badge,
+    metrics: &MetricRegistry,
+    sleep_override: SleepOverride,
+) -> MenuBuilder<Events> {
     let run_on_start_enabled = SettingsManagerImpl::is_run_on_start_enabled();
-    let current_icon = SettingsManagerImpl::get_current_icon();
-    let current_theme = SettingsManagerImpl::get_current_theme();
 
     let mut menu = MenuBuilder::new();
 
     // Build theme submenu - only show if current icon supports themes
-    if icon_manager.supports_themes(&current_icon) {
-        let available_themes = icon_manager.available_themes_for_icon(&current_icon);
+    if icon_manager.supports_themes(current_icon) {
+        let available_themes = icon_manager.available_themes_for_icon(current_icon);
         if !available_themes.is_empty() {
             let mut theme_menu = MenuBuilder::new();
             for theme in available_themes {
                 let theme_name = match theme {
                     Theme::Dark => "Dark",
                     Theme::Light => "Light",
-                    #[cfg(target_os = "macos")]
+                    #[cfg(any(target_os = "macos", target_os = "linux"))]
                     Theme::Auto => "Auto",
                 };
                 let is_current = current_theme == theme;
@@ -41,25 +58,57 @@ pub fn build_menu(icon_manager: &IconManager) -> MenuBuilder<Events> {
         }
     }
 
+    // Build badge submenu - which corner shows the usage percentage, if any
+    let mut badge_menu = MenuBuilder::new();
+    for badge in Badge::ALL {
+        let is_current = current_badge == badge;
+        badge_menu = badge_menu.radio(badge.label(), is_current, Events::SetBadge(badge));
+    }
+    menu = menu.submenu("Usage Badge", badge_menu);
+
     // Build icon submenu - dynamically from available icons
     let available_icons = icon_manager.available_icons();
     if available_icons.len() > 1 {
         let mut icon_menu = MenuBuilder::new();
         for icon_name in available_icons {
             let is_current = current_icon == icon_name;
-            // Capitalize first letter for display
-            let display_name = icon_name
-                .chars()
-                .next()
-                .unwrap()
-                .to_uppercase()
-                .collect::<String>()
-                + &icon_name[1..];
+            let display_name = icon_manager.display_name(&icon_name);
             icon_menu = icon_menu.radio(&display_name, is_current, Events::SetIcon(icon_name));
         }
         menu = menu.submenu("Icon", icon_menu);
     }
 
+    // Build monitor submenu - which metric source drives the animation speed
+    let metric_sources = metrics.list();
+    if metric_sources.len() > 1 {
+        let mut metric_menu = MenuBuilder::new();
+        for (name, label) in metric_sources {
+            let is_current = metrics.active_name() == name;
+            metric_menu = metric_menu.radio(&label, is_current, Events::SetMetric(name));
+        }
+        menu = menu.submenu("Monitor", metric_menu);
+    }
+
+    // Build sensitivity submenu - custom is only offered once a table is configured
+    let speed_curve = SettingsManagerImpl::get_speed_curve();
+    let mut curve_menu = MenuBuilder::new();
+    for kind in CurveKind::ALL {
+        if kind == CurveKind::Custom && speed_curve.points.is_empty() {
+            continue;
+        }
+        let is_current = speed_curve.kind == kind;
+        curve_menu = curve_menu.radio(kind.label(), is_current, Events::SetSpeedCurve(kind));
+    }
+    menu = menu.submenu("Sensitivity", curve_menu);
+
+    // Build sleep submenu - force the cat asleep or awake regardless of the schedule
+    let mut sleep_menu = MenuBuilder::new();
+    for mode in SleepOverride::ALL {
+        let is_current = sleep_override == mode;
+        sleep_menu = sleep_menu.radio(mode.label(), is_current, Events::SetSleepOverride(mode));
+    }
+    menu = menu.submenu("Sleep", sleep_menu);
+
     menu.separator()
         .checkable(
             "Run on Start",
@@ -0,0 +1,402 @@
+//! Animation frames as pixels.
+//!
+//! Packs ship their frames as ICO files, which the tray backends take as
+//! they are but nothing else can look into. An [`IconFrame`] holds every
+//! size entry of one ICO file decoded to straight-alpha RGBA, whether the
+//! entry was stored as a PNG or as a BMP, so frames can be drawn elsewhere
+//! or changed before they reach the tray. [`IconFrame::to_icon`] turns one
+//! back into a tray icon.
+
+use crate::image_frames;
+use std::io::Cursor;
+use std::sync::Arc;
+use trayicon::Icon;
+
+/// ICONDIR plus one ICONDIRENTRY per image
+const DIR_SIZE: usize = 6;
+const ENTRY_SIZE: usize = 16;
+
+/// One size of a frame, straight-alpha RGBA, rows top to bottom
+#[derive(Debug, Clone, PartialEq)]
+pub struct RgbaImage {
+    pub width: u32,
+    pub height: u32,
+    pub pixels: Vec<u8>,
+}
+
+impl RgbaImage {
+    pub fn new(width: u32, height: u32) -> Self {
+        Self {
+            width,
+            height,
+            pixels: vec![0; (width * height * 4) as usize],
+        }
+    }
+
+    pub fn pixel(&self, x: u32, y: u32) -> [u8; 4] {
+        let at = ((y * self.width + x) * 4) as usize;
+        let p = &self.pixels[at..at + 4];
+        [p[0], p[1], p[2], p[3]]
+    }
+
+    pub fn set_pixel(&mut self, x: u32, y: u32, rgba: [u8; 4]) {
+        let at = ((y * self.width + x) * 4) as usize;
+        self.pixels[at..at + 4].copy_from_slice(&rgba);
+    }
+
+    fn to_png(&self) -> Result<Vec<u8>, String> {
+        let mut png = Vec::new();
+        let mut encoder = png::Encoder::new(&mut png, self.width, self.height);
+        encoder.set_color(png::ColorType::Rgba);
+        encoder.set_depth(png::BitDepth::Eight);
+        let mut writer = encoder.write_header().map_err(|e| e.to_string())?;
+        writer
+            .write_image_data(&self.pixels)
+            .map_err(|e| e.to_string())?;
+        writer.finish().map_err(|e| e.to_string())?;
+        Ok(png)
+    }
+}
+
+/// Every size entry of one ICO frame, smallest first
+#[derive(Debug, Clone, PartialEq)]
+pub struct IconFrame {
+    images: Vec<RgbaImage>,
+}
+
+impl IconFrame {
+    /// Decode all entries of an ICO file. Entries hold PNG data or a BMP of
+    /// 1, 4, 8, 24 or 32 bits per pixel.
+    pub fn from_ico(data: &[u8]) -> Result<Self, String> {
+        if !image_frames::is_ico(data) {
+            return Err("not an ICO file".to_string());
+        }
+        let count = read_u16(data, 4).ok_or("truncated ICO header")? as usize;
+        let mut images = Vec::with_capacity(count);
+        for i in 0..count {
+            let entry = DIR_SIZE + ENTRY_SIZE * i;
+            let (Some(size), Some(offset)) =
+                (read_u32(data, entry + 8), read_u32(data, entry + 12))
+            else {
+                return Err(format!("truncated ICO entry {}", i));
+            };
+            let image = data
+                .get(offset as usize..(offset as usize).saturating_add(size as usize))
+                .ok_or(format!("ICO entry {} out of bounds", i))?;
+            let decoded = if image.starts_with(b"\x89PNG\r\n\x1a\n") {
+                decode_png(image)
+            } else {
+                decode_bmp(image)
+            };
+            images.push(decoded.map_err(|e| format!("ICO entry {}: {}", i, e))?);
+        }
+        if images.is_empty() {
+            return Err("ICO file has no entries".to_string());
+        }
+        images.sort_by_key(|image| image.width * image.height);
+        Ok(Self { images })
+    }
+
+    pub fn largest(&self) -> &RgbaImage {
+        // Never empty, see from_ico
+        &self.images[self.images.len() - 1]
+    }
+
+    /// Frame with `f` applied to every size
+    pub fn map_images(&self, f: impl Fn(&RgbaImage) -> RgbaImage) -> Self {
+        Self {
+            images: self.images.iter().map(f).collect(),
+        }
+    }
+
+    /// ICO file with every size as a PNG entry
+    pub fn to_ico(&self) -> Result<Vec<u8>, String> {
+        for image in &self.images {
+            image_frames::check_size(image.width, image.height)?;
+        }
+        let pngs = self
+            .images
+            .iter()
+            .map(RgbaImage::to_png)
+            .collect::<Result<Vec<_>, _>>()?;
+        let mut ico = vec![0, 0, 1, 0];
+        ico.extend_from_slice(&(self.images.len() as u16).to_le_bytes());
+        let mut offset = DIR_SIZE + ENTRY_SIZE * self.images.len();
+        for (image, png) in self.images.iter().zip(&pngs) {
+            // 0 means 256 in the size bytes
+            ico.push((image.width % image_frames::MAX_SIZE) as u8);
+            ico.push((image.height % image_frames::MAX_SIZE) as u8);
+            ico.extend_from_slice(&[0, 0]); // palette size, reserved
+            ico.extend_from_slice(&1u16.to_le_bytes()); // color planes
+            ico.extend_from_slice(&32u16.to_le_bytes()); // bits per pixel
+            ico.extend_from_slice(&(png.len() as u32).to_le_bytes());
+            ico.extend_from_slice(&(offset as u32).to_le_bytes());
+            offset += png.len();
+        }
+        for png in pngs {
+            ico.extend_from_slice(&png);
+        }
+        Ok(ico)
+    }
+
+    /// Tray icon of this frame
+    pub fn to_icon(&self) -> Result<OwnedIcon, String> {
+        let data: Arc<[u8]> = self.to_ico()?.into();
+        // SAFETY: the slice is never written to, and it is freed only with
+        // the last `OwnedIcon` sharing it, after that one's icon
+        let ico: &'static [u8] = unsafe { std::slice::from_raw_parts(data.as_ptr(), data.len()) };
+        // Windows picks the entry for the DPI and the Linux/KDE backend the
+        // largest one; macOS wants 16 pt and keeps the rest for Retina
+        #[cfg(not(target_os = "macos"))]
+        let size = None;
+        #[cfg(target_os = "macos")]
+        let size = Some(16);
+        let icon = Icon::from_buffer(ico, size, size).map_err(|e| e.to_string())?;
+        Ok(OwnedIcon { icon, _data: data })
+    }
+}
+
+/// A tray icon along with the ICO data it borrows. trayicon only takes
+/// `'static` data; instead of leaking it, every clone shares it and the last
+/// one frees it.
+///
+/// The tray keeps its own copy of the icon it shows, without the data, so
+/// hold on to the `OwnedIcon` until another icon replaces it.
+#[derive(Clone)]
+pub struct OwnedIcon {
+    // Dropped before the data it borrows
+    icon: Icon,
+    _data: Arc<[u8]>,
+}
+
+impl OwnedIcon {
+    pub fn icon(&self) -> &Icon {
+        &self.icon
+    }
+
+    /// See `Icon::set_template`
+    #[cfg(target_os = "macos")]
+    pub fn set_template(&mut self, template: bool) {
+        self.icon.set_template(template);
+    }
+}
+
+fn read_u16(data: &[u8], at: usize) -> Option<u16> {
+    data.get(at..at + 2)
+        .map(|b| u16::from_le_bytes([b[0], b[1]]))
+}
+
+fn read_u32(data: &[u8], at: usize) -> Option<u32> {
+    data.get(at..at + 4)
+        .map(|b| u32::from_le_bytes([b[0], b[1], b[2], b[3]]))
+}
+
+fn decode_png(data: &[u8]) -> Result<RgbaImage, String> {
+    let mut decoder = png::Decoder::new(Cursor::new(data));
+    decoder.set_transformations(png::Transformations::normalize_to_color8());
+    let mut reader = decoder.read_info().map_err(|e| e.to_string())?;
+    let mut buf = vec![0; reader.output_buffer_size()];
+    let output = reader.next_frame(&mut buf).map_err(|e| e.to_string())?;
+    Ok(RgbaImage {
+        width: output.width,
+        height: output.height,
+        pixels: image_frames::to_rgba(&buf[..output.buffer_size()], output.color_type)?,
+    })
+}
+
+/// BITMAPINFOHEADER and pixels without the file header. The height covers
+/// the color rows and the 1-bit AND mask below them, which marks the
+/// transparent pixels of images without an alpha channel.
+fn decode_bmp(data: &[u8]) -> Result<RgbaImage, String> {
+    let header = read_u32(data, 0).ok_or("truncated BMP header")? as usize;
+    let width = read_u32(data, 4).ok_or("truncated BMP header")?;
+    // Negative for rows stored top-down; covers the mask rows too
+    let height = read_u32(data, 8).ok_or("truncated BMP header")? as i32;
+    let top_down = height < 0;
+    let height = height.unsigned_abs() / 2;
+    let bpp = read_u16(data, 14).ok_or("truncated BMP header")?;
+    let compression = read_u32(data, 16).ok_or("truncated BMP header")?;
+    if width == 0
+        || height == 0
+        || width > image_frames::MAX_SIZE
+        || height > image_frames::MAX_SIZE
+    {
+        return Err(format!("invalid BMP size {}x{}", width, height));
+    }
+    // BI_RGB, or BI_BITFIELDS with the usual masks
+    if compression != 0 && compression != 3 {
+        return Err(format!(
+            "compressed BMP entries are not supported ({})",
+            compression
+        ));
+    }
+
+    let palette_size = match bpp {
+        1 | 4 | 8 => match read_u32(data, 32).ok_or("truncated BMP header")? {
+            0 => 1 << bpp,
+            used => used as usize,
+        },
+        24 | 32 => 0,
+        _ => return Err(format!("{}-bit BMP entries are not supported", bpp)),
+    };
+    let masks = if compression == 3 { 12 } else { 0 };
+    let palette = data
+        .get(header + masks..header + masks + palette_size * 4)
+        .ok_or("truncated BMP palette")?;
+    let colors_at = header + masks + palette_size * 4;
+    // Rows are padded to 4 bytes and stored bottom-up unless `top_down`
+    let row_at = |y: u32| if top_down { y } else { height - 1 - y } as usize;
+    let stride = (width as usize * bpp as usize).div_ceil(32) * 4;
+    let mask_stride = (width as usize).div_ceil(32) * 4;
+    let mask_at = colors_at + stride * height as usize;
+    let colors = data.get(colors_at..mask_at).ok_or("truncated BMP pixels")?;
+    // Some encoders leave the mask out of 32-bit entries
+    let mask = data.get(mask_at..mask_at + mask_stride * height as usize);
+
+    let mut image = RgbaImage::new(width, height);
+    for y in 0..height {
+        let row = &colors[row_at(y) * stride..][..stride];
+        for x in 0..width {
+            let at = x as usize * bpp as usize / 8;
+            let [b, g, r, a] = match bpp {
+                32 => [row[at], row[at + 1], row[at + 2], row[at + 3]],
+                24 => [row[at], row[at + 1], row[at + 2], 255],
+                _ => {
+                    let shift = 8 - bpp as usize - (x as usize * bpp as usize) % 8;
+                    let index = (row[at] >> shift) as usize & ((1 << bpp) - 1);
+                    let color = palette
+                        .get(index * 4..index * 4 + 3)
+                        .ok_or("BMP palette index out of range")?;
+                    [color[0], color[1], color[2], 255]
+                }
+            };
+            image.set_pixel(x, y, [r, g, b, a]);
+        }
+    }
+
+    // 32-bit entries with an all-zero alpha channel are from before alpha
+    // and rely on the mask like the others
+    let has_alpha = bpp == 32 && image.pixels.chunks_exact(4).any(|p| p[3] != 0);
+    if let (false, Some(mask)) = (has_alpha, mask) {
+        for y in 0..height {
+            let row = &mask[row_at(y) * mask_stride..][..mask_stride];
+            for x in 0..width {
+                let transparent = row[x as usize / 8] & (0x80 >> (x % 8)) != 0;
+                let [r, g, b, _] = image.pixel(x, y);
+                let alpha = if transparent { 0 } else { 255 };
+                image.set_pixel(x, y, [r, g, b, alpha]);
+            }
+        }
+    }
+    Ok(image)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+
+    fn sizes(frame: &IconFrame) -> Vec<(u32, u32)> {
+        frame
+            .images
+            .iter()
+            .map(|image| (image.width, image.height))
+            .collect()
+    }
+
+    #[test]
+    fn test_decode_assets() {
+        for (dir, expected) in [
+            // 32-bit BMP entries
+            ("assets/cat", vec![(16, 16), (32, 32)]),
+            // PNG entries
+            ("assets/parrot", vec![(16, 16), (24, 24), (32, 32)]),
+        ] {
+            let mut files: Vec<_> = fs::read_dir(dir)
+                .unwrap()
+                .map(|entry| entry.unwrap().path())
+                .filter(|path| path.extension().is_some_and(|ext| ext == "ico"))
+                .collect();
+            files.sort();
+            assert!(!files.is_empty());
+            for path in files {
+                let frame = IconFrame::from_ico(&fs::read(&path).unwrap()).unwrap();
+                let largest = frame.largest();
+                assert_eq!(largest.pixels.len(), (32 * 32 * 4) as usize);
+                // The sleeping cat only comes in 32x32
+                if !path.to_string_lossy().contains("sleep") {
+                    assert_eq!(sizes(&frame), expected, "{}", path.display());
+                }
+                assert!(
+                    largest.pixels.chunks_exact(4).any(|p| p[3] == 255),
+                    "{} is blank",
+                    path.display()
+                );
+
+                // What the tray gets decodes to the same pixels
+                let again = IconFrame::from_ico(&frame.to_ico().unwrap()).unwrap();
+                assert_eq!(again, frame);
+            }
+        }
+    }
+
+    #[test]
+    fn test_decode_palette_bmp() {
+        // 2x2, 1 bit per pixel: black and white, with the top right pixel
+        // masked out
+        let mut bmp = Vec::new();
+        bmp.extend_from_slice(&40u32.to_le_bytes());
+        bmp.extend_from_slice(&2u32.to_le_bytes());
+        bmp.extend_from_slice(&4u32.to_le_bytes()); // color rows and mask rows
+        bmp.extend_from_slice(&1u16.to_le_bytes());
+        bmp.extend_from_slice(&1u16.to_le_bytes());
+        bmp.extend_from_slice(&[0; 24]);
+        // Palette
+        bmp.extend_from_slice(&[0, 0, 0, 0, 255, 255, 255, 0]);
+        // Bottom row white, black; top row black, white
+        bmp.extend_from_slice(&[0b1000_0000, 0, 0, 0, 0b0100_0000, 0, 0, 0]);
+        // Mask
+        bmp.extend_from_slice(&[0, 0, 0, 0, 0b0100_0000, 0, 0, 0]);
+        let expected = [
+            [0, 0, 0, 255],
+            [255, 255, 255, 0],
+            [255, 255, 255, 255],
+            [0, 0, 0, 255],
+        ]
+        .concat();
+        assert_eq!(decode_bmp(&bmp).unwrap().pixels, expected);
+
+        // The same image stored top-down, which a negative height says
+        let mut top_down = bmp[..48].to_vec();
+        top_down[8..12].copy_from_slice(&(-4i32).to_le_bytes());
+        top_down.extend_from_slice(&[0b0100_0000, 0, 0, 0, 0b1000_0000, 0, 0, 0]);
+        top_down.extend_from_slice(&[0b0100_0000, 0, 0, 0, 0, 0, 0, 0]);
+        assert_eq!(decode_bmp(&top_down).unwrap().pixels, expected);
+
+        assert!(IconFrame::from_ico(b"GIF89a").is_err());
+    }
+
+    #[test]
+    fn test_large_entries_are_refused() {
+        let mut frame =
+            IconFrame::from_ico(&fs::read("assets/cat/dark_cat_0.ico").unwrap()).unwrap();
+        frame.images.push(RgbaImage::new(256, 256));
+        let ico = frame.to_ico().unwrap();
+        // 0 stands for 256
+        assert_eq!(IconFrame::from_ico(&ico).unwrap(), frame);
+        frame.images.push(RgbaImage::new(300, 300));
+        assert!(frame.to_ico().is_err());
+    }
+
+    #[test]
+    fn test_icon_data_is_freed() {
+        let frame = IconFrame::from_ico(&fs::read("assets/cat/dark_cat_0.ico").unwrap()).unwrap();
+        let icon = frame.to_icon().unwrap();
+        let data = Arc::downgrade(&icon._data);
+        let clone = icon.clone();
+        drop(icon);
+        assert!(data.upgrade().is_some());
+        drop(clone);
+        assert!(data.upgrade().is_none());
+    }
+}
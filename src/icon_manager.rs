@@ -1,7 +1,11 @@
+use crate::debug;
+use crate::icon_frame::{IconFrame, OwnedIcon};
+use crate::image_frames;
+use crate::pack_manifest::PackManifest;
 use flate2::read::GzDecoder;
 use std::collections::HashMap;
 use std::io::Read;
-use trayicon::Icon;
+use std::path::{Path, PathBuf};
 
 #[allow(dead_code)]
 mod icon_data {
@@ -9,7 +13,9 @@ mod icon_data {
         pub offset: usize,
         pub sizes: &'static [u32],
     }
-    pub type IconData = HashMap<&'static str, HashMap<&'static str, IconGroupInfo>>;
+    // icon name -> state -> theme -> frames
+    pub type IconData =
+        HashMap<&'static str, HashMap<&'static str, HashMap<&'static str, IconGroupInfo>>>;
     include!(concat!(env!("OUT_DIR"), "/icon_data.rs"));
 }
 
@@ -17,16 +23,18 @@ mod icon_data {
 pub enum Theme {
     Dark,
     Light,
-    #[cfg(target_os = "macos")]
+    /// Follows the system: template icons on macOS, the desktop's live
+    /// light/dark preference on Linux
+    #[cfg(any(target_os = "macos", target_os = "linux"))]
     Auto,
 }
 
 impl Theme {
-    #[cfg(target_os = "macos")]
+    #[cfg(any(target_os = "macos", target_os = "linux"))]
     pub fn from_system() -> Self {
         Theme::Auto
     }
-    #[cfg(not(target_os = "macos"))]
+    #[cfg(windows)]
     pub fn from_system() -> Self {
         use crate::platform::{SettingsManager, SettingsManagerImpl};
         if SettingsManagerImpl::is_dark_mode_enabled() {
@@ -35,6 +43,31 @@ impl Theme {
             Theme::Light
         }
     }
+
+    /// Parse the name written by `Display`
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "dark" => Some(Theme::Dark),
+            "light" => Some(Theme::Light),
+            #[cfg(any(target_os = "macos", target_os = "linux"))]
+            "auto" => Some(Theme::Auto),
+            _ => None,
+        }
+    }
+
+    /// Theme whose frames are shown. On Linux `Auto` has no frames of its
+    /// own and stands for whatever the desktop prefers right now.
+    pub fn resolve(self) -> Self {
+        #[cfg(target_os = "linux")]
+        if self == Theme::Auto {
+            return if crate::platform::linux::appearance::prefers_dark() {
+                Theme::Dark
+            } else {
+                Theme::Light
+            };
+        }
+        self
+    }
 }
 
 impl std::fmt::Display for Theme {
@@ -42,17 +75,58 @@ impl std::fmt::Display for Theme {
         match self {
             Theme::Dark => write!(f, "dark"),
             Theme::Light => write!(f, "light"),
-            #[cfg(target_os = "macos")]
+            #[cfg(any(target_os = "macos", target_os = "linux"))]
             Theme::Auto => write!(f, "auto"),
         }
     }
 }
 
+/// Which set of frames a pack shows. Every pack has running frames; the
+/// other states are optional and fall back to running when missing.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum AnimationState {
+    Running,
+    Sleeping,
+    /// Under heavy load
+    Alert,
+}
+
+impl AnimationState {
+    /// Parse the state part of an icon file name (`run` for the main frames)
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "run" => Some(AnimationState::Running),
+            "sleep" => Some(AnimationState::Sleeping),
+            "alert" => Some(AnimationState::Alert),
+            _ => None,
+        }
+    }
+}
+
+impl AnimationState {
+    /// State name as used in file names and `pack.toml`
+    pub fn name(&self) -> &'static str {
+        match self {
+            AnimationState::Running => "run",
+            AnimationState::Sleeping => "sleep",
+            AnimationState::Alert => "alert",
+        }
+    }
+}
+
+impl std::fmt::Display for AnimationState {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.name())
+    }
+}
+
 pub struct IconManager {
-    // Maps base icon name -> theme -> icon frames
-    icon_sets: HashMap<String, HashMap<Theme, Vec<Icon>>>,
+    // Maps base icon name -> state -> icon frames per theme
+    icon_sets: HashMap<String, HashMap<AnimationState, ThemeFrames>>,
     // Maps base icon name -> whether it supports themes
     theme_support: HashMap<String, bool>,
+    // Maps base icon name -> pack manifest (display name, timing, tooltips)
+    manifests: HashMap<String, PackManifest>,
 }
 
 impl IconManager {
@@ -60,6 +134,7 @@ impl IconManager {
         Self {
             icon_sets: HashMap::new(),
             theme_support: HashMap::new(),
+            manifests: HashMap::new(),
         }
     }
 
@@ -79,130 +154,135 @@ impl IconManager {
         // Get icon metadata from build script generated module
         let icon_metadata_map = icon_data::get_icon_metadata();
 
-        fn load_icons(
-            icon_name: &str,
-            theme_str: &str,
-            data: &'static [u8],
-            offset: usize,
-            sizes: &[u32],
-        ) -> Result<Vec<Icon>, String> {
-            let mut icons = Vec::new();
-            let mut current_offset = offset as usize;
-
-            for &size in sizes {
-                let size = size as usize;
-                if current_offset + size > data.len() {
-                    return Err(format!(
-                        "Invalid icon offset/size data: {} + {} exceeds data length",
-                        current_offset, size
-                    ));
-                }
+        for &(icon_name, text) in icon_data::MANIFESTS {
+            let manifest = PackManifest::parse(text)
+                .map_err(|e| format!("Invalid manifest for {}: {}", icon_name, e))?;
+            manager
+                .theme_support
+                .insert(icon_name.to_string(), manifest.themes.len() > 1);
+            manager.manifests.insert(icon_name.to_string(), manifest);
+        }
 
-                let icon_data = &data[current_offset..current_offset + size];
-                let icon = {
-                    #[cfg(windows)]
-                    {
-                        Icon::from_buffer(icon_data, None, None).map_err(|e| {
-                            format!(
-                                "Failed to create icon from buffer for {} {}: {}",
-                                icon_name, theme_str, e
-                            )
-                        })
-                    }
-                    #[cfg(target_os = "macos")]
-                    {
-                        // macOS tray icons should be smaller (16x16 is the standard size)
-                        Icon::from_buffer(icon_data, Some(16), Some(16)).map_err(|e| {
-                            format!(
-                                "Failed to create icon from buffer for {} {}: {}",
-                                icon_name, theme_str, e
-                            )
-                        })
-                    }
-                    #[cfg(target_os = "linux")]
-                    {
-                        // The Linux/KDE backend decodes the ICO and picks the
-                        // largest entry automatically; width/height hints are ignored.
-                        Icon::from_buffer(icon_data, None, None).map_err(|e| {
-                            format!(
-                                "Failed to create icon from buffer for {} {}: {}",
-                                icon_name, theme_str, e
-                            )
-                        })
-                    }
-                }?;
-
-                icons.push(icon);
-                current_offset += size;
+        for (icon_name, state_data) in icon_metadata_map {
+            let mut states_map = HashMap::new();
+
+            for (&state_str, theme_data) in &state_data {
+                let Some(state) = AnimationState::from_name(state_str) else {
+                    continue; // Skip unknown states
+                };
+                let manifest = &manager.manifests[icon_name];
+                let mut frames = Vec::new();
+                for (&theme_str, group_info) in theme_data {
+                    let buffers =
+                        split_frames(all_decompressed, group_info.offset, group_info.sizes)?
+                            .into_iter()
+                            .enumerate()
+                            .map(|(i, data)| (data, manifest.duration(state_str, i)))
+                            .collect();
+                    frames.push((theme_str, buffers));
+                }
+                states_map.insert(state, load_theme_frames(icon_name, &frames)?);
             }
+            manager.icon_sets.insert(icon_name.to_string(), states_map);
+        }
 
-            Ok(icons)
+        if let Some(dir) = user_packs_dir() {
+            manager.load_user_packs(&dir);
         }
 
-        for (icon_name, theme_data) in icon_metadata_map {
-            // All current icons support themes
-            manager.theme_support.insert(icon_name.to_string(), true);
-            let mut themes_map = HashMap::new();
+        Ok(manager)
+    }
 
-            for (&theme_str, group_info) in &theme_data {
-                let theme = match theme_str {
-                    "dark" => Theme::Dark,
-                    "light" => Theme::Light,
-                    _ => continue, // Skip unknown themes
-                };
-                let icons = load_icons(
-                    icon_name,
-                    theme_str,
-                    all_decompressed,
-                    group_info.offset,
-                    group_info.sizes,
-                )?;
-
-                themes_map.insert(theme, icons);
+    /// Add every pack found under `dir`, one sub-directory per pack. A pack is
+    /// described by its `pack.toml`, or without one by the
+    /// `<theme>_<name>_<n>.ico` / `<theme>_<name>_<state>_<n>.ico` naming.
+    /// A user pack replaces a built-in pack of the same name. Broken packs
+    /// are reported and skipped.
+    pub fn load_user_packs(&mut self, dir: &Path) {
+        let entries = match std::fs::read_dir(dir) {
+            Ok(entries) => entries,
+            // No user packs installed
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return,
+            Err(e) => {
+                eprintln!(
+                    "Failed to read icon pack directory {}: {}",
+                    dir.display(),
+                    e
+                );
+                return;
             }
+        };
+
+        let mut pack_dirs: Vec<PathBuf> = entries
+            .filter_map(|entry| entry.ok().map(|e| e.path()))
+            .filter(|path| path.is_dir())
+            .collect();
+        pack_dirs.sort();
 
-            #[cfg(target_os = "macos")]
-            {
-                // macOS icons are always themed because Auto are generated from first theme
-                let group_info = theme_data
-                    .get("light")
-                    .or_else(|| theme_data.get("dark"))
-                    .unwrap();
-                let mut icons = load_icons(
-                    icon_name,
-                    "auto",
-                    all_decompressed,
-                    group_info.offset,
-                    group_info.sizes,
-                )?;
-                for icon in &mut icons {
-                    icon.set_template(true);
+        for pack_dir in pack_dirs {
+            let Some(name) = pack_dir.file_name().and_then(|n| n.to_str()) else {
+                continue;
+            };
+            match load_user_pack(name, &pack_dir) {
+                Ok((states_map, manifest)) => {
+                    debug!("Loaded icon pack '{}' from {}", name, pack_dir.display());
+                    self.theme_support
+                        .insert(name.to_string(), manifest.themes.len() > 1);
+                    self.icon_sets.insert(name.to_string(), states_map);
+                    self.manifests.insert(name.to_string(), manifest);
                 }
-                manager.theme_support.insert(icon_name.to_string(), true);
-                themes_map.insert(Theme::Auto, icons);
+                Err(e) => eprintln!("Skipping icon pack '{}': {}", name, e),
             }
-            manager.icon_sets.insert(icon_name.to_string(), themes_map);
         }
+    }
 
-        Ok(manager)
+    pub fn get_icon_set(&self, icon_name: &str, theme: Option<Theme>) -> Option<&Vec<OwnedIcon>> {
+        self.get_state_icon_set(icon_name, AnimationState::Running, theme)
+    }
+
+    /// Frames of a pack for a state. Returns `None` if the pack has no
+    /// frames of its own for that state; see [`Self::has_state`].
+    pub fn get_state_icon_set(
+        &self,
+        icon_name: &str,
+        state: AnimationState,
+        theme: Option<Theme>,
+    ) -> Option<&Vec<OwnedIcon>> {
+        let frames = self.icon_sets.get(icon_name)?.get(&state)?;
+        self.pick_theme(icon_name, &frames.icons, theme)
+    }
+
+    /// Pixels of the frames [`Self::get_state_icon_set`] returns
+    pub fn get_state_frames(
+        &self,
+        icon_name: &str,
+        state: AnimationState,
+        theme: Option<Theme>,
+    ) -> Option<&Vec<IconFrame>> {
+        let frames = self.icon_sets.get(icon_name)?.get(&state)?;
+        self.pick_theme(icon_name, &frames.frames, theme)
     }
 
-    pub fn get_icon_set(&self, icon_name: &str, theme: Option<Theme>) -> Option<&Vec<Icon>> {
-        let icon_map = self.icon_sets.get(icon_name)?;
+    fn pick_theme<'a, T>(
+        &self,
+        icon_name: &str,
+        theme_map: &'a HashMap<Theme, T>,
+        theme: Option<Theme>,
+    ) -> Option<&'a T> {
+        if !self.supports_themes(icon_name) {
+            // Single theme pack, use whatever it has
+            return theme_map.values().next();
+        }
 
         if let Some(theme) = theme {
             // Specific theme requested
-            icon_map.get(&theme)
+            theme_map.get(&theme.resolve())
         } else {
             // Auto-detect theme or use first available
-            if self.supports_themes(icon_name) {
-                let system_theme = Theme::from_system();
-                icon_map
-                    .get(&system_theme)
-                    .or_else(|| icon_map.values().next()) // Fallback to any theme
-            } else {
-                icon_map.values().next() // Single theme icon
-            }
+            let system_theme = Theme::from_system().resolve();
+            theme_map
+                .get(&system_theme)
+                .or_else(|| theme_map.values().next()) // Fallback to any theme
         }
     }
 
@@ -210,22 +290,70 @@ impl IconManager {
         self.theme_support.get(icon_name).copied().unwrap_or(false)
     }
 
+    /// Whether a pack provides its own frames for `state`
+    pub fn has_state(&self, icon_name: &str, state: AnimationState) -> bool {
+        self.icon_sets
+            .get(icon_name)
+            .is_some_and(|states| states.contains_key(&state))
+    }
+
+    /// Tooltip a pack wants shown while in `state`, if any
+    pub fn state_tooltip(&self, icon_name: &str, state: AnimationState) -> Option<&str> {
+        self.manifests
+            .get(icon_name)?
+            .states
+            .get(state.name())?
+            .tooltip
+            .as_deref()
+    }
+
+    /// Multiplier of the frame delay for frame `index` of a state, from the
+    /// pack manifest and the delays stored in animated GIF/APNG frames
+    pub fn frame_duration(
+        &self,
+        icon_name: &str,
+        state: AnimationState,
+        theme: Option<Theme>,
+        index: usize,
+    ) -> f64 {
+        self.icon_sets
+            .get(icon_name)
+            .and_then(|states| states.get(&state))
+            .and_then(|frames| self.pick_theme(icon_name, &frames.durations, theme))
+            .and_then(|durations| durations.get(index))
+            .copied()
+            .unwrap_or(1.0)
+    }
+
+    /// Name of a pack for the menu
+    pub fn display_name(&self, icon_name: &str) -> String {
+        self.manifests
+            .get(icon_name)
+            .map_or_else(|| icon_name.to_string(), |m| m.name.clone())
+    }
+
     pub fn available_icons(&self) -> Vec<String> {
-        let mut icons: Vec<String> = self.icon_sets.keys()
-            .filter(|&name| name != "sleep") // Hide sleep icons from menu
-            .cloned()
-            .collect();
+        let mut icons: Vec<String> = self.icon_sets.keys().cloned().collect();
         icons.sort();
         icons
     }
 
     pub fn available_themes_for_icon(&self, icon_name: &str) -> Vec<Theme> {
-        if let Some(icon_map) = self.icon_sets.get(icon_name) {
-            let mut themes: Vec<Theme> = icon_map.keys().copied().collect();
+        if let Some(frames) = self
+            .icon_sets
+            .get(icon_name)
+            .and_then(|states| states.get(&AnimationState::Running))
+        {
+            let mut themes: Vec<Theme> = frames.icons.keys().copied().collect();
+            // Auto switches between the pack's own dark and light frames
+            #[cfg(target_os = "linux")]
+            if themes.contains(&Theme::Dark) && themes.contains(&Theme::Light) {
+                themes.push(Theme::Auto);
+            }
             themes.sort_by_key(|t| match t {
                 Theme::Dark => 0,
                 Theme::Light => 1,
-                #[cfg(target_os = "macos")]
+                #[cfg(any(target_os = "macos", target_os = "linux"))]
                 Theme::Auto => 2,
             });
             themes
@@ -257,6 +385,186 @@ impl IconManager {
     }
 }
 
+/// Directory scanned for user icon packs, e.g. `~/.config/rustcat/icons`
+pub fn user_packs_dir() -> Option<PathBuf> {
+    dirs::config_dir().map(|dir| dir.join("rustcat").join("icons"))
+}
+
+/// Frame data with its delay multiplier
+type TimedFrame<'a> = (&'a [u8], f64);
+
+/// Frames of one animation state
+struct ThemeFrames {
+    icons: HashMap<Theme, Vec<OwnedIcon>>,
+    /// Decoded frames the icons were made from
+    frames: HashMap<Theme, Vec<IconFrame>>,
+    /// Frame delay multiplier of every icon
+    durations: HashMap<Theme, Vec<f64>>,
+}
+
+/// Read one user pack directory, described by its `pack.toml` or, without
+/// one, by its file names.
+fn load_user_pack(
+    name: &str,
+    dir: &Path,
+) -> Result<(HashMap<AnimationState, ThemeFrames>, PackManifest), String> {
+    let manifest = PackManifest::load(name, dir)?;
+    let missing = manifest.missing_frames(dir);
+    if !missing.is_empty() {
+        return Err(format!("missing frames: {}", missing.join(", ")));
+    }
+
+    let mut states_map = HashMap::new();
+    for state_str in manifest.states.keys() {
+        let Some(state) = AnimationState::from_name(state_str) else {
+            continue; // Rejected by validation already
+        };
+        let mut files = Vec::new();
+        for theme in &manifest.themes {
+            let buffers = manifest
+                .frame_files(state_str, theme)
+                .iter()
+                .enumerate()
+                .map(|(i, file)| {
+                    let data =
+                        std::fs::read(dir.join(file)).map_err(|e| format!("{}: {}", file, e))?;
+                    Ok((data, manifest.duration(state_str, i)))
+                })
+                .collect::<Result<Vec<_>, String>>()?;
+            files.push((theme.as_str(), buffers));
+        }
+        let frames: Vec<(&str, Vec<TimedFrame>)> = files
+            .iter()
+            .map(|(theme, buffers)| {
+                let buffers = buffers
+                    .iter()
+                    .map(|(data, duration)| (data.as_slice(), *duration))
+                    .collect();
+                (*theme, buffers)
+            })
+            .collect();
+        states_map.insert(state, load_theme_frames(name, &frames)?);
+    }
+    Ok((states_map, manifest))
+}
+
+/// Cut a group of frames out of the decompressed built-in chunk.
+fn split_frames(
+    data: &'static [u8],
+    offset: usize,
+    sizes: &[u32],
+) -> Result<Vec<&'static [u8]>, String> {
+    let mut frames = Vec::new();
+    let mut current_offset = offset;
+
+    for &size in sizes {
+        let size = size as usize;
+        if current_offset + size > data.len() {
+            return Err(format!(
+                "Invalid icon offset/size data: {} + {} exceeds data length",
+                current_offset, size
+            ));
+        }
+        frames.push(&data[current_offset..current_offset + size]);
+        current_offset += size;
+    }
+
+    Ok(frames)
+}
+
+/// Turn `(theme, [(frame buffer, duration)])` pairs of one state into
+/// decoded frames and tray icons. Animated GIF and APNG buffers expand into
+/// several frames.
+fn load_theme_frames(
+    icon_name: &str,
+    frames: &[(&str, Vec<TimedFrame>)],
+) -> Result<ThemeFrames, String> {
+    let mut theme_frames = ThemeFrames {
+        icons: HashMap::new(),
+        frames: HashMap::new(),
+        durations: HashMap::new(),
+    };
+
+    let mut expanded = Vec::new();
+    for (theme_str, buffers) in frames {
+        let mut theme_buffers = Vec::new();
+        for &(data, duration) in buffers {
+            theme_buffers.extend(expand_frame(data, duration).map_err(|e| {
+                format!("Failed to load frame of {} {}: {}", icon_name, theme_str, e)
+            })?);
+        }
+        expanded.push((*theme_str, theme_buffers));
+    }
+
+    for (theme_str, buffers) in &expanded {
+        let theme = match *theme_str {
+            "dark" => Theme::Dark,
+            "light" => Theme::Light,
+            _ => continue, // Skip unknown themes
+        };
+        let icons = buffers
+            .iter()
+            .map(|(frame, _)| to_icon(frame, icon_name, theme_str))
+            .collect::<Result<Vec<_>, _>>()?;
+        theme_frames.icons.insert(theme, icons);
+        theme_frames.frames.insert(
+            theme,
+            buffers.iter().map(|(frame, _)| frame.clone()).collect(),
+        );
+        theme_frames
+            .durations
+            .insert(theme, buffers.iter().map(|(_, d)| *d).collect());
+    }
+
+    #[cfg(target_os = "macos")]
+    {
+        // macOS icons are always themed because Auto are generated from first theme
+        let (_, buffers) = expanded
+            .iter()
+            .find(|(theme, _)| *theme == "light")
+            .or_else(|| expanded.first())
+            .ok_or_else(|| format!("No frames for {}", icon_name))?;
+        let mut icons = buffers
+            .iter()
+            .map(|(frame, _)| to_icon(frame, icon_name, "auto"))
+            .collect::<Result<Vec<_>, _>>()?;
+        for icon in &mut icons {
+            icon.set_template(true);
+        }
+        theme_frames.icons.insert(Theme::Auto, icons);
+        theme_frames.frames.insert(
+            Theme::Auto,
+            buffers.iter().map(|(frame, _)| frame.clone()).collect(),
+        );
+        theme_frames
+            .durations
+            .insert(Theme::Auto, buffers.iter().map(|(_, d)| *d).collect());
+    }
+
+    Ok(theme_frames)
+}
+
+/// ICO buffers are decoded as they are; anything else goes through
+/// [`image_frames`] first and may turn into several frames.
+fn expand_frame(data: &[u8], duration: f64) -> Result<Vec<(IconFrame, f64)>, String> {
+    if image_frames::is_ico(data) {
+        return Ok(vec![(IconFrame::from_ico(data)?, duration)]);
+    }
+    image_frames::to_ico_frames(data)?
+        .into_iter()
+        .map(|frame| Ok((IconFrame::from_ico(&frame.ico)?, duration * frame.duration)))
+        .collect()
+}
+
+fn to_icon(frame: &IconFrame, icon_name: &str, theme_str: &str) -> Result<OwnedIcon, String> {
+    frame.to_icon().map_err(|e| {
+        format!(
+            "Failed to create icon from buffer for {} {}: {}",
+            icon_name, theme_str, e
+        )
+    })
+}
+
 impl Default for IconManager {
     fn default() -> Self {
         Self::new()
@@ -274,4 +582,55 @@ mod tests {
         assert!(!manager.supports_themes("parrot"));
         assert!(!manager.supports_themes(""));
     }
+
+    #[test]
+    fn test_load_user_packs() {
+        let dir = std::env::temp_dir().join(format!("rustcat-packs-{}", std::process::id()));
+        let pack = dir.join("mascot");
+        std::fs::create_dir_all(&pack).unwrap();
+        let frame = std::fs::read("assets/cat/dark_cat_0.ico").unwrap();
+        for file in ["dark_mascot_0.ico", "dark_mascot_1.ico"] {
+            std::fs::write(pack.join(file), &frame).unwrap();
+        }
+        std::fs::copy(
+            "assets/sleep/dark_sleep1.png",
+            pack.join("dark_mascot_sleep_0.png"),
+        )
+        .unwrap();
+        // No running frames, skipped
+        std::fs::create_dir_all(dir.join("broken")).unwrap();
+        // Described by a manifest instead of file names
+        let bird = dir.join("bird");
+        std::fs::create_dir_all(&bird).unwrap();
+        std::fs::write(bird.join("up.ico"), &frame).unwrap();
+        std::fs::write(bird.join("down.ico"), &frame).unwrap();
+        std::fs::write(
+            bird.join("pack.toml"),
+            "name = \"Head Bob\"\nthemes = [\"light\"]\n[states.run]\n\
+             frames = [\"up.ico\", \"down.ico\", \"up.ico\"]\ndurations = [1.0, 3.0, 1.0]\n",
+        )
+        .unwrap();
+
+        let mut manager = IconManager::new();
+        manager.load_user_packs(&dir);
+        std::fs::remove_dir_all(&dir).unwrap();
+
+        assert_eq!(manager.available_icons(), ["bird", "mascot"]);
+        assert_eq!(manager.display_name("mascot"), "Mascot");
+        assert_eq!(manager.display_name("bird"), "Head Bob");
+        assert_eq!(
+            manager.frame_duration("bird", AnimationState::Running, None, 1),
+            3.0
+        );
+        assert_eq!(manager.get_icon_set("bird", None).map(Vec::len), Some(3));
+        assert!(!manager.supports_themes("mascot"));
+        assert!(manager.has_state("mascot", AnimationState::Sleeping));
+        // A single theme pack answers for any theme
+        assert_eq!(
+            manager
+                .get_icon_set("mascot", Some(Theme::Light))
+                .map(Vec::len),
+            Some(2)
+        );
+    }
 }
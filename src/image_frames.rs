@@ -0,0 +1,347 @@
+//! Turns PNG, GIF and APNG files into ICO frames.
+//!
+//! Every tray backend accepts ICO buffers and ICO entries may hold PNG data,
+//! so a static PNG only needs an ICO directory in front of it. Animated GIF
+//! and APNG files are composited frame by frame, re-encoded as PNG and
+//! wrapped the same way. Each frame keeps its own delay, relative to the
+//! file's average delay.
+
+use std::io::Cursor;
+
+/// Largest frame an ICO directory entry can describe
+pub const MAX_SIZE: u32 = 256;
+/// Browsers play zero-delay frames at 100 ms; so do we.
+const DEFAULT_DELAY: f64 = 0.1;
+
+pub struct Frame {
+    /// ICO file with a single PNG entry
+    pub ico: Vec<u8>,
+    /// Delay multiplier, 1.0 being the file's average frame delay
+    pub duration: f64,
+}
+
+pub fn is_ico(data: &[u8]) -> bool {
+    data.starts_with(&[0, 0, 1, 0])
+}
+
+/// Convert a PNG, APNG or GIF file into ICO frames.
+pub fn to_ico_frames(data: &[u8]) -> Result<Vec<Frame>, String> {
+    if data.starts_with(b"\x89PNG\r\n\x1a\n") {
+        from_png(data)
+    } else if data.starts_with(b"GIF8") {
+        from_gif(data)
+    } else {
+        Err("unsupported image format (expected ICO, PNG or GIF)".to_string())
+    }
+}
+
+fn from_png(data: &[u8]) -> Result<Vec<Frame>, String> {
+    let mut decoder = png::Decoder::new(Cursor::new(data));
+    decoder.set_transformations(png::Transformations::normalize_to_color8());
+    let mut reader = decoder.read_info().map_err(|e| e.to_string())?;
+    let (width, height) = (reader.info().width, reader.info().height);
+    check_size(width, height)?;
+
+    let Some(animation) = reader.info().animation_control else {
+        // Static PNG, usable as is
+        return Ok(vec![Frame {
+            ico: wrap_png(data, width, height),
+            duration: 1.0,
+        }]);
+    };
+
+    // Without an fcTL before IDAT the default image is not part of the
+    // animation and only shown by decoders that don't know APNG
+    let skip_default = reader.info().frame_control.is_none();
+    let mut buf = vec![0; reader.output_buffer_size()];
+    if skip_default {
+        reader.next_frame(&mut buf).map_err(|e| e.to_string())?;
+    }
+
+    let mut canvas = Canvas::new(width, height);
+    let mut frames = Vec::new();
+    for _ in 0..animation.num_frames {
+        let output = reader.next_frame(&mut buf).map_err(|e| e.to_string())?;
+        let control = reader
+            .info()
+            .frame_control
+            .ok_or("APNG frame without fcTL")?;
+        let rgba = to_rgba(&buf[..output.buffer_size()], output.color_type)?;
+        let area = Area {
+            x: control.x_offset,
+            y: control.y_offset,
+            width: control.width,
+            height: control.height,
+        };
+
+        let previous = (control.dispose_op == png::DisposeOp::Previous).then(|| canvas.clone());
+        canvas.draw(&rgba, area, control.blend_op == png::BlendOp::Over);
+        let den = if control.delay_den == 0 {
+            100
+        } else {
+            control.delay_den
+        };
+        frames.push((canvas.to_png()?, control.delay_num as f64 / den as f64));
+
+        match control.dispose_op {
+            png::DisposeOp::None => {}
+            png::DisposeOp::Background => canvas.clear(area),
+            // There is nothing to restore before the first frame
+            png::DisposeOp::Previous if frames.len() == 1 => canvas.clear(area),
+            png::DisposeOp::Previous => canvas = previous.unwrap_or(canvas),
+        }
+    }
+    Ok(finish(frames, width, height))
+}
+
+fn from_gif(data: &[u8]) -> Result<Vec<Frame>, String> {
+    let mut options = gif::DecodeOptions::new();
+    options.set_color_output(gif::ColorOutput::RGBA);
+    let mut decoder = options
+        .read_info(Cursor::new(data))
+        .map_err(|e| e.to_string())?;
+    let (width, height) = (decoder.width() as u32, decoder.height() as u32);
+    check_size(width, height)?;
+
+    let mut canvas = Canvas::new(width, height);
+    let mut frames = Vec::new();
+    while let Some(frame) = decoder.read_next_frame().map_err(|e| e.to_string())? {
+        let area = Area {
+            x: frame.left as u32,
+            y: frame.top as u32,
+            width: frame.width as u32,
+            height: frame.height as u32,
+        };
+
+        let previous = (frame.dispose == gif::DisposalMethod::Previous).then(|| canvas.clone());
+        // Transparent pixels leave the canvas alone
+        canvas.draw(&frame.buffer, area, true);
+        frames.push((canvas.to_png()?, frame.delay as f64 / 100.0));
+
+        match frame.dispose {
+            gif::DisposalMethod::Background => canvas.clear(area),
+            gif::DisposalMethod::Previous => canvas = previous.unwrap_or(canvas),
+            gif::DisposalMethod::Any | gif::DisposalMethod::Keep => {}
+        }
+    }
+    if frames.is_empty() {
+        return Err("GIF has no frames".to_string());
+    }
+    Ok(finish(frames, width, height))
+}
+
+/// Wrap encoded frames and turn their delays into multipliers.
+fn finish(frames: Vec<(Vec<u8>, f64)>, width: u32, height: u32) -> Vec<Frame> {
+    let delay = |d: f64| if d > 0.0 { d } else { DEFAULT_DELAY };
+    let average = frames.iter().map(|(_, d)| delay(*d)).sum::<f64>() / frames.len() as f64;
+    frames
+        .into_iter()
+        .map(|(png, d)| Frame {
+            ico: wrap_png(&png, width, height),
+            duration: delay(d) / average,
+        })
+        .collect()
+}
+
+pub fn check_size(width: u32, height: u32) -> Result<(), String> {
+    if width == 0 || height == 0 || width > MAX_SIZE || height > MAX_SIZE {
+        return Err(format!(
+            "{}x{} image, icons must be at most {}x{}",
+            width, height, MAX_SIZE, MAX_SIZE
+        ));
+    }
+    Ok(())
+}
+
+/// ICO file with one entry holding `png`.
+fn wrap_png(png: &[u8], width: u32, height: u32) -> Vec<u8> {
+    const HEADER_SIZE: u32 = 6 + 16;
+    let mut ico = Vec::with_capacity(HEADER_SIZE as usize + png.len());
+    // ICONDIR: reserved, type 1 (icon), one entry
+    ico.extend_from_slice(&[0, 0, 1, 0, 1, 0]);
+    // ICONDIRENTRY: 0 means 256 in the size bytes
+    ico.push((width % MAX_SIZE) as u8);
+    ico.push((height % MAX_SIZE) as u8);
+    ico.extend_from_slice(&[0, 0]); // palette size, reserved
+    ico.extend_from_slice(&1u16.to_le_bytes()); // color planes
+    ico.extend_from_slice(&32u16.to_le_bytes()); // bits per pixel
+    ico.extend_from_slice(&(png.len() as u32).to_le_bytes());
+    ico.extend_from_slice(&HEADER_SIZE.to_le_bytes());
+    ico.extend_from_slice(png);
+    ico
+}
+
+/// Expand decoded 8-bit PNG output to RGBA.
+pub fn to_rgba(data: &[u8], color_type: png::ColorType) -> Result<Vec<u8>, String> {
+    Ok(match color_type {
+        png::ColorType::Rgba => data.to_vec(),
+        png::ColorType::Rgb => data
+            .chunks_exact(3)
+            .flat_map(|p| [p[0], p[1], p[2], 255])
+            .collect(),
+        png::ColorType::GrayscaleAlpha => data
+            .chunks_exact(2)
+            .flat_map(|p| [p[0], p[0], p[0], p[1]])
+            .collect(),
+        png::ColorType::Grayscale => data.iter().flat_map(|&g| [g, g, g, 255]).collect(),
+        png::ColorType::Indexed => return Err("unexpanded palette image".to_string()),
+    })
+}
+
+#[derive(Debug, Clone, Copy)]
+struct Area {
+    x: u32,
+    y: u32,
+    width: u32,
+    height: u32,
+}
+
+/// RGBA buffer animation frames are composited on
+#[derive(Clone)]
+struct Canvas {
+    width: u32,
+    height: u32,
+    pixels: Vec<u8>,
+}
+
+impl Canvas {
+    fn new(width: u32, height: u32) -> Self {
+        Self {
+            width,
+            height,
+            pixels: vec![0; (width * height * 4) as usize],
+        }
+    }
+
+    /// Draw an RGBA sub-image, replacing the area or alpha blending over it.
+    /// Parts outside the canvas are cut off.
+    fn draw(&mut self, rgba: &[u8], area: Area, blend: bool) {
+        if rgba.len() < (area.width * area.height * 4) as usize {
+            return;
+        }
+        let rows = area.height.min(self.height.saturating_sub(area.y));
+        let cols = area.width.min(self.width.saturating_sub(area.x));
+        for row in 0..rows {
+            for col in 0..cols {
+                let src = ((row * area.width + col) * 4) as usize;
+                let dst = (((area.y + row) * self.width + area.x + col) * 4) as usize;
+                let src = &rgba[src..src + 4];
+                let out = if blend {
+                    over(src, &self.pixels[dst..dst + 4])
+                } else {
+                    [src[0], src[1], src[2], src[3]]
+                };
+                self.pixels[dst..dst + 4].copy_from_slice(&out);
+            }
+        }
+    }
+
+    fn clear(&mut self, area: Area) {
+        self.draw(
+            &vec![0; (area.width * area.height * 4) as usize],
+            area,
+            false,
+        );
+    }
+
+    fn to_png(&self) -> Result<Vec<u8>, String> {
+        let mut png = Vec::new();
+        let mut encoder = png::Encoder::new(&mut png, self.width, self.height);
+        encoder.set_color(png::ColorType::Rgba);
+        encoder.set_depth(png::BitDepth::Eight);
+        let mut writer = encoder.write_header().map_err(|e| e.to_string())?;
+        writer
+            .write_image_data(&self.pixels)
+            .map_err(|e| e.to_string())?;
+        writer.finish().map_err(|e| e.to_string())?;
+        Ok(png)
+    }
+}
+
+/// Porter-Duff "source over destination" for straight-alpha RGBA.
+fn over(src: &[u8], dst: &[u8]) -> [u8; 4] {
+    let sa = src[3] as u32;
+    if sa == 255 {
+        return [src[0], src[1], src[2], 255];
+    }
+    if sa == 0 {
+        return [dst[0], dst[1], dst[2], dst[3]];
+    }
+    let da = dst[3] as u32 * (255 - sa) / 255;
+    let out_a = sa + da;
+    let channel = |i: usize| ((src[i] as u32 * sa + dst[i] as u32 * da) / out_a) as u8;
+    [channel(0), channel(1), channel(2), out_a as u8]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn embedded_png(ico: &[u8]) -> (u32, u32, Vec<u8>) {
+        assert!(is_ico(ico));
+        let decoder = png::Decoder::new(Cursor::new(&ico[22..]));
+        let mut reader = decoder.read_info().unwrap();
+        let mut buf = vec![0; reader.output_buffer_size()];
+        let info = reader.next_frame(&mut buf).unwrap();
+        (info.width, info.height, buf)
+    }
+
+    #[test]
+    fn test_static_png_is_wrapped() {
+        let data = std::fs::read("assets/sleep/dark_sleep1.png").unwrap();
+        let frames = to_ico_frames(&data).unwrap();
+        assert_eq!(frames.len(), 1);
+        assert_eq!(&frames[0].ico[22..], &data[..]);
+    }
+
+    #[test]
+    fn test_gif_frames_keep_their_delays() {
+        let mut gif = Vec::new();
+        {
+            let palette = [0, 0, 0, 255, 0, 0];
+            let mut encoder = gif::Encoder::new(&mut gif, 2, 1, &palette).unwrap();
+            for (pixels, delay) in [([1u8, 0], 10), ([0, 1], 30)] {
+                let mut frame = gif::Frame::from_indexed_pixels(2, 1, pixels.to_vec(), None);
+                frame.delay = delay;
+                encoder.write_frame(&frame).unwrap();
+            }
+        }
+
+        let frames = to_ico_frames(&gif).unwrap();
+        assert_eq!(frames.len(), 2);
+        assert!((frames[0].duration - 0.5).abs() < 1e-9);
+        assert!((frames[1].duration - 1.5).abs() < 1e-9);
+        let (width, height, pixels) = embedded_png(&frames[1].ico);
+        assert_eq!((width, height), (2, 1));
+        assert_eq!(pixels, [0, 0, 0, 255, 255, 0, 0, 255]);
+    }
+
+    #[test]
+    fn test_apng_subframes_are_composited() {
+        let mut apng = Vec::new();
+        {
+            let mut encoder = png::Encoder::new(&mut apng, 2, 1);
+            encoder.set_color(png::ColorType::Rgba);
+            encoder.set_depth(png::BitDepth::Eight);
+            encoder.set_animated(2, 0).unwrap();
+            encoder.set_frame_delay(1, 10).unwrap();
+            let mut writer = encoder.write_header().unwrap();
+            writer
+                .write_image_data(&[255, 0, 0, 255, 0, 0, 0, 0])
+                .unwrap();
+            // Second frame only covers the right pixel and blends over the first
+            writer.set_frame_dimension(1, 1).unwrap();
+            writer.set_frame_position(1, 0).unwrap();
+            writer.set_blend_op(png::BlendOp::Over).unwrap();
+            writer.set_frame_delay(3, 10).unwrap();
+            writer.write_image_data(&[0, 255, 0, 255]).unwrap();
+            writer.finish().unwrap();
+        }
+
+        let frames = to_ico_frames(&apng).unwrap();
+        assert_eq!(frames.len(), 2);
+        assert!((frames[1].duration - 1.5).abs() < 1e-9);
+        let (_, _, pixels) = embedded_png(&frames[1].ico);
+        assert_eq!(pixels, [255, 0, 0, 255, 0, 255, 0, 255]);
+    }
+}
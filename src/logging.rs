@@ -9,11 +9,16 @@
 //!
 //! Genuine errors stay on stderr unconditionally: they are rare and are
 //! exactly what you want in the journal when something goes wrong.
+//!
+//! Modes whose stdout is their output, like `stream`, move the informational
+//! logs to stderr with [`to_stderr`].
 
 use std::io::IsTerminal;
+use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::OnceLock;
 
 static ENABLED: OnceLock<bool> = OnceLock::new();
+static TO_STDERR: AtomicBool = AtomicBool::new(false);
 
 /// `true` when informational logging should be emitted.
 ///
@@ -40,14 +45,59 @@ pub fn enabled() -> bool {
     })
 }
 
-/// Emit an informational line to stdout, but only when debug logging is
-/// enabled (TTY or opt-in env var). Uses `print!` so callers control the
-/// trailing newline.
+/// Turn informational logging on regardless of the TTY and environment, for
+/// `--debug`. Has no effect once [`enabled`] has been called.
+pub fn enable() {
+    let _ = ENABLED.set(true);
+}
+
+/// Turn informational logging off regardless of the TTY and environment, for
+/// modes that draw on the terminal. Has no effect after [`enable`].
+pub fn disable() {
+    let _ = ENABLED.set(false);
+}
+
+/// Write informational logs to stderr from now on, keeping stdout free for
+/// the output of the mode. Call it before anything is logged.
+pub fn to_stderr() {
+    TO_STDERR.store(true, Ordering::Relaxed);
+}
+
+/// `true` once [`to_stderr`] moved informational logs off stdout
+pub fn on_stderr() -> bool {
+    TO_STDERR.load(Ordering::Relaxed)
+}
+
+/// Emit an informational line, but only when debug logging is enabled (TTY
+/// or opt-in env var). Goes to stdout unless `logging::to_stderr` was called.
 #[macro_export]
 macro_rules! debug {
     ($($arg:tt)*) => {
         if $crate::logging::enabled() {
+            $crate::info!($($arg)*);
+        }
+    };
+}
+
+/// Tell the user about something done on their behalf, such as upgrading the
+/// settings file. Always emitted, on stdout unless `logging::to_stderr` was
+/// called.
+#[macro_export]
+macro_rules! info {
+    ($($arg:tt)*) => {
+        if $crate::logging::on_stderr() {
+            eprintln!($($arg)*);
+        } else {
             println!($($arg)*);
         }
     };
-}
\ No newline at end of file
+}
+
+/// Report a problem the user can fix, such as a bad settings value. Always
+/// emitted, on stderr, like errors.
+#[macro_export]
+macro_rules! warn {
+    ($($arg:tt)*) => {
+        eprintln!("Warning: {}", format_args!($($arg)*))
+    };
+}
@@ -1,15 +1,35 @@
 #![cfg_attr(all(not(debug_assertions), windows), windows_subsystem = "windows")]
 
 mod app;
+mod badge;
+mod cli;
 mod events;
+mod icon_frame;
 mod icon_manager;
+mod image_frames;
 mod logging;
+mod metrics;
+#[cfg(target_os = "linux")]
+mod migrations;
+mod pack_manifest;
 mod platform;
+mod schedule;
+#[cfg(target_os = "linux")]
+mod settings;
+mod smoothing;
+mod speed_curve;
+mod stream;
+mod tui;
 
 use crate::{
+    app::App,
+    cli::Command,
+    events::Events,
     icon_manager::IconManager,
+    metrics::MetricRegistry,
     platform::{SettingsManager, SettingsManagerImpl, SystemIntegration, SystemIntegrationImpl},
 };
+use std::process::exit;
 
 #[cfg(target_os = "macos")]
 use crate::platform::macos::app::MacosApp;
@@ -19,15 +39,142 @@ use crate::platform::linux::app::LinuxApp;
 use crate::platform::windows::app::WindowsApp;
 
 fn main() {
+    let options = match cli::parse(std::env::args().skip(1)) {
+        Ok(options) => options,
+        Err(e) => {
+            eprintln!("rust_cat: {}\n\n{}", e, cli::USAGE);
+            exit(2);
+        }
+    };
+    if options.debug {
+        logging::enable();
+    }
+    match options.command {
+        // stdout is the bar's input, anything else there breaks it
+        Command::Stream => logging::to_stderr(),
+        // stderr shares the terminal the cat is drawn on, so only --debug logs
+        Command::Tui => {
+            logging::to_stderr();
+            logging::disable();
+        }
+        _ => {}
+    }
+    #[cfg(target_os = "linux")]
+    if let Some(path) = &options.config {
+        if let Err(e) = platform::linux::settings::set_settings_path(path) {
+            eprintln!("Invalid settings path {}: {}", path.display(), e);
+            exit(1);
+        }
+    }
+
+    match options.command {
+        Command::Help => {
+            print!("{}", cli::USAGE);
+            return;
+        }
+        Command::Version => {
+            println!("{}", cli::version());
+            return;
+        }
+        Command::ResetSettings => {
+            if let Err(e) = SettingsManagerImpl::reset_settings() {
+                eprintln!("Failed to reset the settings: {}", e);
+                exit(1);
+            }
+            println!("Reset {} to the defaults", SettingsManagerImpl::location());
+            return;
+        }
+        Command::Run | Command::Status | Command::ListIcons | Command::Stream | Command::Tui => {}
+    }
+
     // Migrate legacy settings if needed
     SettingsManagerImpl::migrate_legacy_settings();
 
     // Load icons
     let icon_manager = IconManager::load_icons().expect("Failed to load icons");
 
-    // Get current icon and theme
-    let icon_name = SettingsManagerImpl::get_current_icon();
-    let theme = SettingsManagerImpl::get_current_theme();
+    // Get current icon, command-line override first
+    let icon_name = match options.icon.clone() {
+        Some(icon_name) if icon_manager.available_icons().contains(&icon_name) => icon_name,
+        Some(icon_name) => {
+            eprintln!(
+                "Icon pack '{}' not found, see rust_cat list-icons",
+                icon_name
+            );
+            exit(1);
+        }
+        None => {
+            let icon_name = SettingsManagerImpl::get_current_icon();
+            if icon_manager.available_icons().contains(&icon_name) {
+                icon_name
+            } else {
+                // e.g. a user icon pack that has since been removed
+                eprintln!("Icon pack '{}' not found, falling back to cat", icon_name);
+                "cat".to_string()
+            }
+        }
+    };
+    if let Some(metric) = &options.metric {
+        let known: Vec<String> = MetricRegistry::with_platform_sources()
+            .list()
+            .into_iter()
+            .map(|(name, _)| name)
+            .collect();
+        if !known.contains(metric) {
+            eprintln!(
+                "Unknown metric '{}', expected one of: {}",
+                metric,
+                known.join(", ")
+            );
+            exit(1);
+        }
+    }
+
+    match options.command {
+        Command::Status => {
+            let metric = options
+                .metric
+                .unwrap_or_else(SettingsManagerImpl::get_metric);
+            let theme = options
+                .theme
+                .unwrap_or_else(SettingsManagerImpl::get_current_theme);
+            cli::print_status(&icon_name, theme, &metric);
+            return;
+        }
+        Command::ListIcons => {
+            cli::list_icons(&icon_manager, &icon_name);
+            return;
+        }
+        Command::Stream => {
+            run_stream(icon_manager, &icon_name, &options);
+            return;
+        }
+        Command::Tui => {
+            run_tui(icon_manager, &icon_name, &options);
+            return;
+        }
+        Command::Run => {}
+        Command::Help | Command::Version | Command::ResetSettings => unreachable!(),
+    }
+
+    let show_tray = !options.no_tray;
+    // Only one cat per session, headless or not, since both publish the same
+    // D-Bus name; a later launch hands its request over
+    #[cfg(target_os = "linux")]
+    let instance = {
+        use platform::linux::instance;
+        match instance::acquire(&instance::requests(&options)) {
+            Ok(Some(instance)) => Some(instance),
+            Ok(None) => {
+                println!("RustCat is already running, passed the request on to it");
+                return;
+            }
+            Err(e) => {
+                eprintln!("Failed to check for a running RustCat: {}", e);
+                None
+            }
+        }
+    };
 
     std::panic::set_hook(Box::new(|e| {
         let msg = format!("Panic: {}", e);
@@ -36,14 +183,72 @@ fn main() {
         }
     }));
 
+    let overrides = options.overrides();
     #[cfg(target_os = "windows")]
-    let app = WindowsApp::new(icon_manager, &icon_name, Some(theme)).expect("Failed to create app");
+    let app = WindowsApp::new(icon_manager, &icon_name, overrides, show_tray)
+        .expect("Failed to create app");
     #[cfg(target_os = "macos")]
-    let app = MacosApp::new(icon_manager, &icon_name, Some(theme)).expect("Failed to create app");
+    let app = MacosApp::new(icon_manager, &icon_name, overrides, show_tray)
+        .expect("Failed to create app");
+    #[cfg(target_os = "linux")]
+    let app = LinuxApp::new(icon_manager, &icon_name, overrides, show_tray)
+        .expect("Failed to create app");
     #[cfg(target_os = "linux")]
-    let app = LinuxApp::new(icon_manager, &icon_name, Some(theme)).expect("Failed to create app");
+    let server = instance.map(|instance| instance.serve(app.event_sender(), app.status()));
 
     app.start_animation_thread();
 
     app.run();
+
+    #[cfg(target_os = "linux")]
+    if let Some(server) = server {
+        server.shutdown();
+    }
+}
+
+/// `stream`: the animation as status-bar text on stdout, without a tray icon.
+/// Runs alongside a tray instance rather than handing over to it.
+fn run_stream(icon_manager: IconManager, icon_name: &str, options: &cli::Options) {
+    let app = App::new(icon_manager, icon_name, options.overrides(), false)
+        .expect("Failed to create app");
+    let frames = app.subscribe_frames();
+    let events = app.event_sender();
+    let (format, glyphs) = (options.format, options.glyphs);
+    std::thread::spawn(move || {
+        if let Err(e) = stream::write_stream(frames, format, glyphs, std::io::stdout().lock()) {
+            // Usually the bar closing the pipe on reload
+            debug!("Stopped streaming: {}", e);
+        }
+        let _ = events.send(Events::Exit);
+    });
+    app.start_animation_thread();
+    app.run();
+}
+
+/// `tui`: the animation drawn in the terminal, without a tray icon
+fn run_tui(icon_manager: IconManager, icon_name: &str, options: &cli::Options) {
+    let app = App::new(icon_manager, icon_name, options.overrides(), false)
+        .expect("Failed to create app");
+    let renderer = options.renderer.unwrap_or_else(tui::Renderer::detect);
+    debug!("Drawing with {:?}", renderer);
+    let frames = app.subscribe_frames();
+    let status = app.status();
+    let events = app.event_sender();
+    std::thread::spawn(move || {
+        if let Err(e) = tui::write_frames(frames, status, renderer, std::io::stdout()) {
+            eprintln!("Failed to draw the cat: {}", e);
+        }
+        let _ = events.send(Events::Exit);
+    });
+    let events = app.event_sender();
+    std::thread::spawn(move || {
+        // Any line quits, without having to put the terminal in raw mode
+        let _ = std::io::stdin().read_line(&mut String::new());
+        let _ = events.send(Events::Exit);
+    });
+    app.start_animation_thread();
+    app.run();
+    if let Err(e) = tui::finish(renderer, std::io::stdout()) {
+        debug!("Failed to clean up the terminal: {}", e);
+    }
 }
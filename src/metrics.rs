@@ -0,0 +1,177 @@
+use std::io;
+
+use crate::platform::{metric_sources, MetricSource, Reading};
+
+/// Source idle detection falls back to when the animation follows something
+/// other than the CPU. Memory rarely drops low enough to count as idle, so
+/// the cat would never fall asleep if sleep followed it.
+pub const IDLE_SOURCE: &str = "cpu";
+
+/// Whether the source named `name` measures the CPU, like `cpu` and
+/// `cpu-busiest`, and so can tell idle on its own
+fn is_cpu_source(name: &str) -> bool {
+    name == IDLE_SOURCE || name.starts_with("cpu-")
+}
+
+/// Runtime list of [`MetricSource`]s with one of them active.
+///
+/// The animation thread samples the active source; the menu and settings
+/// refer to sources by [`MetricSource::name`] and can switch between them
+/// while the app is running.
+pub struct MetricRegistry {
+    sources: Vec<Box<dyn MetricSource>>,
+    active: usize,
+}
+
+impl MetricRegistry {
+    pub fn new() -> Self {
+        Self {
+            sources: Vec::new(),
+            active: 0,
+        }
+    }
+
+    /// Registry holding every source this platform provides.
+    pub fn with_platform_sources() -> Self {
+        let mut registry = Self::new();
+        for source in metric_sources() {
+            registry.register(source);
+        }
+        registry
+    }
+
+    /// Add a source. A source with the same name replaces the existing one.
+    pub fn register(&mut self, source: Box<dyn MetricSource>) {
+        match self.position(source.name()) {
+            Some(index) => self.sources[index] = source,
+            None => self.sources.push(source),
+        }
+    }
+
+    /// `(name, label)` of every registered source, in registration order.
+    pub fn list(&self) -> Vec<(String, String)> {
+        self.sources
+            .iter()
+            .map(|s| (s.name().to_string(), s.label().to_string()))
+            .collect()
+    }
+
+    pub fn active_name(&self) -> &str {
+        self.sources.get(self.active).map_or("", |s| s.name())
+    }
+
+    /// Make `name` the active source. Returns `false` (and leaves the active
+    /// source alone) if no source has that name.
+    pub fn set_active(&mut self, name: &str) -> bool {
+        match self.position(name) {
+            Some(index) => {
+                self.active = index;
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Sample the active source and describe the reading for the tooltip.
+    pub fn sample(&mut self) -> io::Result<(Reading, String)> {
+        let source = self
+            .sources
+            .get_mut(self.active)
+            .ok_or_else(|| io::Error::other("No metric source registered"))?;
+        let reading = source.sample()?;
+        let reading = Reading {
+            value: reading.value.clamp(0.0, 100.0),
+            ..reading
+        };
+        let description = source.describe(&reading);
+        Ok((reading, description))
+    }
+
+    /// Value for idle detection. `active` is the reading
+    /// [`sample`](Self::sample) just returned, which is used as is when the
+    /// active source measures the CPU. A busy core keeps `cpu-busiest` awake
+    /// even when the average across all cores looks idle. Other sources fall
+    /// back to [`IDLE_SOURCE`].
+    pub fn idle_value(&mut self, active: &Reading) -> io::Result<f64> {
+        if is_cpu_source(self.active_name()) {
+            return Ok(active.value);
+        }
+        match self.position(IDLE_SOURCE) {
+            Some(index) => Ok(self.sources[index].sample()?.value.clamp(0.0, 100.0)),
+            None => Ok(active.value),
+        }
+    }
+
+    fn position(&self, name: &str) -> Option<usize> {
+        self.sources.iter().position(|s| s.name() == name)
+    }
+}
+
+impl Default for MetricRegistry {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    struct Fixed(&'static str, f64);
+
+    impl MetricSource for Fixed {
+        fn name(&self) -> &str {
+            self.0
+        }
+
+        fn label(&self) -> &str {
+            self.0
+        }
+
+        fn sample(&mut self) -> io::Result<Reading> {
+            Ok(Reading {
+                value: self.1,
+                raw: self.1,
+            })
+        }
+    }
+
+    #[test]
+    fn test_switching_active_source() {
+        let mut registry = MetricRegistry::new();
+        registry.register(Box::new(Fixed("a", 10.0)));
+        registry.register(Box::new(Fixed("b", 250.0)));
+        assert_eq!(registry.active_name(), "a");
+
+        assert!(!registry.set_active("missing"));
+        assert!(registry.set_active("b"));
+        let (reading, description) = registry.sample().unwrap();
+        assert_eq!(reading.value, 100.0);
+        assert_eq!(description, "b Usage: 250.00%");
+    }
+
+    #[test]
+    fn test_idle_follows_cpu() {
+        let mut registry = MetricRegistry::new();
+        registry.register(Box::new(Fixed("cpu", 2.0)));
+        registry.register(Box::new(Fixed("memory", 80.0)));
+        let (reading, _) = registry.sample().unwrap();
+        assert_eq!(registry.idle_value(&reading).unwrap(), 2.0);
+
+        registry.set_active("memory");
+        let (reading, _) = registry.sample().unwrap();
+        assert_eq!(reading.value, 80.0);
+        assert_eq!(registry.idle_value(&reading).unwrap(), 2.0);
+    }
+
+    #[test]
+    fn test_busiest_core_is_its_own_idle_signal() {
+        // One pegged core out of sixteen
+        let mut registry = MetricRegistry::new();
+        registry.register(Box::new(Fixed("cpu", 6.25)));
+        registry.register(Box::new(Fixed("cpu-busiest", 100.0)));
+        registry.set_active("cpu-busiest");
+        let (reading, _) = registry.sample().unwrap();
+        assert_eq!(registry.idle_value(&reading).unwrap(), 100.0);
+    }
+}
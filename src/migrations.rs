@@ -0,0 +1,186 @@
+//! Upgrades of the settings file between formats.
+//!
+//! Every settings file records the `version` of its format. When RustCat
+//! finds an older one it runs the steps below in order, starting at that
+//! version, and the caller backs up the original before writing the result.
+//!
+//! Historical formats:
+//!
+//! - 0: `settings.conf`, untyped `Key=Value` lines
+//! - 1: `settings.toml` from before the version was recorded
+//! - 2: `settings.toml` with `version = 2`
+//!
+//! A format change needs a new step here and a fixture in
+//! `tests/fixtures/settings`.
+
+use crate::settings::{legacy_path, legacy_value};
+use toml::{Table, Value};
+
+/// Version written by this build
+pub const CURRENT_VERSION: u32 = 2;
+
+struct Migration {
+    /// Version the step upgrades from, to `from + 1`
+    from: u32,
+    /// Updates the document, returning a line for every change it made
+    apply: fn(&mut Table) -> Vec<String>,
+}
+
+const MIGRATIONS: &[Migration] = &[
+    Migration {
+        from: 0,
+        apply: conf_to_toml,
+    },
+    Migration {
+        from: 1,
+        // Same layout, the version number is the only addition
+        apply: |_| Vec::new(),
+    },
+];
+
+/// Version of a parsed `settings.toml`
+pub fn version_of(table: &Table) -> u32 {
+    table
+        .get("version")
+        .and_then(Value::as_integer)
+        .and_then(|version| u32::try_from(version).ok())
+        // Files from before versioning had no version key
+        .unwrap_or(1)
+}
+
+/// Version 0 document from the lines of a `settings.conf`
+pub fn parse_conf(text: &str) -> Table {
+    text.lines()
+        .filter_map(|line| line.split_once('='))
+        .map(|(key, value)| {
+            (
+                key.trim().to_string(),
+                Value::String(value.trim().to_string()),
+            )
+        })
+        .collect()
+}
+
+/// Upgrade `table` from `version` to [`CURRENT_VERSION`], returning a
+/// description of every change
+pub fn migrate(table: &mut Table, version: u32) -> Vec<String> {
+    let mut changes = Vec::new();
+    for step in MIGRATIONS.iter().filter(|step| step.from >= version) {
+        let to = step.from + 1;
+        for change in (step.apply)(table) {
+            changes.push(format!("{} -> {}: {}", step.from, to, change));
+        }
+        table.insert("version".to_string(), Value::Integer(to.into()));
+    }
+    changes
+}
+
+/// 0 -> 1: `Key=Value` lines to the sectioned `settings.toml` layout
+fn conf_to_toml(table: &mut Table) -> Vec<String> {
+    let old = std::mem::take(table);
+    let mut changes = Vec::new();
+    for (key, value) in &old {
+        let text = value.as_str().unwrap_or_default();
+        match legacy_path(key) {
+            Some(path) => {
+                insert_path(table, path, legacy_value(path, text));
+                changes.push(format!("{} became {}", key, path));
+            }
+            None => changes.push(format!("dropped unknown key {}={}", key, text)),
+        }
+    }
+    changes
+}
+
+/// Insert at a `section.key` or top-level `key` path
+fn insert_path(table: &mut Table, path: &str, value: Value) {
+    match path.split_once('.') {
+        Some((section, key)) => {
+            let section = table
+                .entry(section)
+                .or_insert_with(|| Value::Table(Table::new()));
+            if let Value::Table(section) = section {
+                section.insert(key.to_string(), value);
+            }
+        }
+        None => {
+            table.insert(path.to_string(), value);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::icon_manager::Theme;
+    use crate::schedule::SleepSchedule;
+    use crate::settings::Settings;
+    use crate::speed_curve::CurveKind;
+
+    fn load_fixture(name: &str) -> (Settings, Vec<String>) {
+        let text = std::fs::read_to_string(format!("tests/fixtures/settings/{}", name)).unwrap();
+        let (mut table, version) = if name.ends_with(".conf") {
+            (parse_conf(&text), 0)
+        } else {
+            let table: Table = text.parse().unwrap();
+            let version = version_of(&table);
+            (table, version)
+        };
+        let changes = migrate(&mut table, version);
+        assert_eq!(version_of(&table), CURRENT_VERSION);
+        let (settings, warnings) = Settings::from_table(&table);
+        assert!(warnings.is_empty(), "{}: {:?}", name, warnings);
+        (settings, changes)
+    }
+
+    #[test]
+    fn test_conf_formats() {
+        let (settings, changes) = load_fixture("v0-icon-theme.conf");
+        assert_eq!(settings.icon, "parrot");
+        assert_eq!(settings.theme, Some(Theme::Light));
+        assert_eq!(
+            changes,
+            ["0 -> 1: IconName became icon", "0 -> 1: Theme became theme"]
+        );
+
+        let (settings, changes) = load_fixture("v0-full.conf");
+        assert!(changes.contains(&"0 -> 1: dropped unknown key SomethingOld=1".to_string()));
+        assert_eq!(settings, expected_full());
+    }
+
+    #[test]
+    fn test_toml_formats() {
+        let (settings, changes) = load_fixture("v1-unversioned.toml");
+        assert_eq!(settings, expected_full());
+        assert!(changes.is_empty());
+
+        let (settings, _) = load_fixture("v2.toml");
+        assert_eq!(settings, expected_full());
+        assert_eq!(
+            std::fs::read_to_string("tests/fixtures/settings/v2.toml").unwrap(),
+            expected_full().to_toml(),
+            "the current format changed, add a migration and a fixture for it"
+        );
+    }
+
+    /// What `v0-full.conf` holds
+    fn expected_full() -> Settings {
+        let mut settings = Settings {
+            icon: "parrot".to_string(),
+            theme: Some(Theme::Dark),
+            metric: "memory".to_string(),
+            ..Settings::default()
+        };
+        settings.speed_curve.kind = CurveKind::Custom;
+        settings.speed_curve.min_delay = 20;
+        settings.speed_curve.max_delay = 300;
+        settings.speed_curve.points = vec![(0.0, 300), (50.0, 80), (100.0, 20)];
+        settings.smoothing.time_constant = 4.0;
+        settings.smoothing.median_window = 3;
+        settings.smoothing.hysteresis = 1.5;
+        settings.sleep.schedule = SleepSchedule::parse("mon-fri 22:00-06:00").unwrap();
+        settings.sleep.idle_seconds = 120;
+        settings.sleep.idle_percent = 3.0;
+        settings
+    }
+}
@@ -0,0 +1,439 @@
+//! `pack.toml`, the manifest describing an icon pack.
+//!
+//! ```toml
+//! name = "Parrot"
+//! author = "Someone"
+//! themes = ["light", "dark"]
+//!
+//! [states.run]
+//! # `{theme}` is replaced by each entry of `themes`
+//! frames = ["{theme}_parrot_0.ico", "{theme}_parrot_1.ico"]
+//! # Optional per-frame multipliers of the frame delay
+//! durations = [1.0, 2.0]
+//!
+//! [states.sleep]
+//! frames = ["{theme}_parrot_sleep_0.ico"]
+//! tooltip = "Zzz"
+//! ```
+//!
+//! Packs without a manifest are described by their file names instead, see
+//! [`PackManifest::scan`].
+//!
+//! This file is shared with `build.rs`, so it must not depend on anything
+//! else in the crate.
+
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::path::Path;
+
+/// State names a pack may define. `run` is required.
+pub const KNOWN_STATES: [&str; 3] = ["run", "sleep", "alert"];
+/// Themes a pack may come in
+pub const KNOWN_THEMES: [&str; 2] = ["light", "dark"];
+/// Frame files picked up from packs without a `pack.toml`
+pub const FRAME_EXTENSIONS: [&str; 4] = ["ico", "png", "gif", "apng"];
+
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+#[serde(deny_unknown_fields)]
+pub struct PackManifest {
+    /// Name shown in the Icon menu
+    pub name: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub author: Option<String>,
+    #[serde(default = "default_themes")]
+    pub themes: Vec<String>,
+    pub states: BTreeMap<String, StateAnimation>,
+}
+
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+#[serde(deny_unknown_fields)]
+pub struct StateAnimation {
+    /// Frame file names in play order, relative to the pack directory
+    pub frames: Vec<String>,
+    /// Frame delay multiplier per frame; empty means 1.0 for every frame
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub durations: Vec<f64>,
+    /// Tooltip shown instead of the metric description while in this state
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub tooltip: Option<String>,
+}
+
+fn default_themes() -> Vec<String> {
+    KNOWN_THEMES.iter().map(|t| t.to_string()).collect()
+}
+
+impl PackManifest {
+    /// Parse and validate a manifest.
+    pub fn parse(text: &str) -> Result<Self, String> {
+        let manifest: PackManifest = toml::from_str(text).map_err(|e| e.to_string())?;
+        manifest.validate()?;
+        Ok(manifest)
+    }
+
+    /// Manifest of the pack in `dir`: its `pack.toml`, or without one a
+    /// manifest built from the `<theme>_<name>_<n>.ico` /
+    /// `<theme>_<name>_<state>_<n>.ico` file names. Frame files are not
+    /// checked, see [`PackManifest::missing_frames`].
+    pub fn load(name: &str, dir: &Path) -> Result<Self, String> {
+        let manifest_path = dir.join("pack.toml");
+        if manifest_path.exists() {
+            let text = std::fs::read_to_string(&manifest_path)
+                .map_err(|e| format!("{}: {}", manifest_path.display(), e))?;
+            Self::parse(&text).map_err(|e| format!("{}: {}", manifest_path.display(), e))
+        } else {
+            Self::scan(name, dir)
+        }
+    }
+
+    /// Manifest for a pack directory without `pack.toml`, from its file names.
+    /// Frames count up from 0 without gaps, and every theme must have the
+    /// same frames in every state.
+    pub fn scan(name: &str, dir: &Path) -> Result<Self, String> {
+        // (state, theme) -> (frame number, file name)
+        let mut found: BTreeMap<(&str, &str), Vec<(usize, String)>> = BTreeMap::new();
+
+        let entries = std::fs::read_dir(dir).map_err(|e| format!("{}: {}", dir.display(), e))?;
+        for entry in entries.filter_map(Result::ok) {
+            let Ok(file_name) = entry.file_name().into_string() else {
+                continue;
+            };
+            if let Some((theme, state, index)) = parse_frame_name(name, &file_name) {
+                found
+                    .entry((state, theme))
+                    .or_default()
+                    .push((index, file_name));
+            }
+        }
+
+        if !found.keys().any(|(state, _)| *state == "run") {
+            return Err(format!(
+                "no pack.toml and no <theme>_{}_<n>.ico frames found",
+                name
+            ));
+        }
+
+        let themes: Vec<&str> = KNOWN_THEMES
+            .into_iter()
+            .filter(|theme| found.keys().any(|(_, t)| t == theme))
+            .collect();
+        let mut groups: Vec<(&str, &str, Vec<String>)> = Vec::new();
+        let mut missing: Vec<String> = Vec::new();
+        for ((state, theme), mut files) in found {
+            files.sort();
+            // The same frame in two formats, e.g. dark_cat_0.ico and dark_cat_0.png
+            if let Some(pair) = files.windows(2).find(|pair| pair[0].0 == pair[1].0) {
+                return Err(format!(
+                    "{} and {} are both frame {}",
+                    pair[0].1, pair[1].1, pair[0].0
+                ));
+            }
+            let (last, example) = &files[files.len() - 1];
+            for index in (0..*last).filter(|index| !files.iter().any(|(i, _)| i == index)) {
+                missing.push(frame_name(example, *last, index));
+            }
+            groups.push((
+                theme,
+                state,
+                files.into_iter().map(|(_, file)| file).collect(),
+            ));
+        }
+
+        // A frame of one theme needs its counterpart in every other theme
+        for (theme, state, files) in &groups {
+            for other in &themes {
+                let counterpart = groups
+                    .iter()
+                    .find(|(t, s, _)| t == other && s == state)
+                    .map_or(&[][..], |(_, _, files)| &files[..]);
+                for file in files {
+                    let expected = format!("{}{}", other, &file[theme.len()..]);
+                    if !counterpart.contains(&expected) && !missing.contains(&expected) {
+                        missing.push(expected);
+                    }
+                }
+            }
+        }
+        if !missing.is_empty() {
+            return Err(format!("missing frames {}", missing.join(", ")));
+        }
+
+        let manifest = Self::from_convention(name, &groups);
+        manifest.validate()?;
+        Ok(manifest)
+    }
+
+    /// Manifest for a pack without `pack.toml`, from its frame files
+    /// grouped as `(theme, state, file names in play order)`. Every file name
+    /// starts with `<theme>_`.
+    pub fn from_convention(name: &str, groups: &[(&str, &str, Vec<String>)]) -> Self {
+        let mut themes: Vec<String> = Vec::new();
+        let mut states: BTreeMap<String, StateAnimation> = BTreeMap::new();
+        for (theme, state, files) in groups {
+            if !themes.iter().any(|t| t == theme) {
+                themes.push(theme.to_string());
+            }
+            states
+                .entry(state.to_string())
+                .or_insert_with(|| StateAnimation {
+                    frames: files
+                        .iter()
+                        .map(|file| format!("{{theme}}{}", &file[theme.len()..]))
+                        .collect(),
+                    durations: Vec::new(),
+                    tooltip: None,
+                });
+        }
+        themes.sort();
+        PackManifest {
+            name: display_name(name),
+            author: None,
+            themes,
+            states,
+        }
+    }
+
+    pub fn validate(&self) -> Result<(), String> {
+        if self.name.trim().is_empty() {
+            return Err("name must not be empty".to_string());
+        }
+        if self.themes.is_empty() {
+            return Err("themes must not be empty".to_string());
+        }
+        if let Some(theme) = self
+            .themes
+            .iter()
+            .find(|t| !KNOWN_THEMES.contains(&t.as_str()))
+        {
+            return Err(format!("unknown theme '{}'", theme));
+        }
+        if !self.states.contains_key("run") {
+            return Err("missing [states.run]".to_string());
+        }
+        for (state, animation) in &self.states {
+            if !KNOWN_STATES.contains(&state.as_str()) {
+                return Err(format!("unknown state '{}'", state));
+            }
+            if animation.frames.is_empty() {
+                return Err(format!("state '{}' has no frames", state));
+            }
+            if !animation.durations.is_empty() {
+                if animation.durations.len() != animation.frames.len() {
+                    return Err(format!(
+                        "state '{}' has {} frames but {} durations",
+                        state,
+                        animation.frames.len(),
+                        animation.durations.len()
+                    ));
+                }
+                if animation
+                    .durations
+                    .iter()
+                    .any(|d| !d.is_finite() || *d <= 0.0)
+                {
+                    return Err(format!("state '{}' has a non-positive duration", state));
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Frame file names of `state` for `theme`, in play order.
+    pub fn frame_files(&self, state: &str, theme: &str) -> Vec<String> {
+        self.states.get(state).map_or_else(Vec::new, |animation| {
+            animation
+                .frames
+                .iter()
+                .map(|frame| frame.replace("{theme}", theme))
+                .collect()
+        })
+    }
+
+    /// Frame files referenced by the manifest that do not exist in `dir`.
+    pub fn missing_frames(&self, dir: &Path) -> Vec<String> {
+        let mut missing = Vec::new();
+        for state in self.states.keys() {
+            for theme in &self.themes {
+                for file in self.frame_files(state, theme) {
+                    if !dir.join(&file).is_file() && !missing.contains(&file) {
+                        missing.push(file);
+                    }
+                }
+            }
+        }
+        missing
+    }
+
+    /// Delay multiplier of frame `index` of `state`, 1.0 if unspecified.
+    pub fn duration(&self, state: &str, index: usize) -> f64 {
+        self.states
+            .get(state)
+            .and_then(|animation| animation.durations.get(index))
+            .copied()
+            .unwrap_or(1.0)
+    }
+}
+
+/// Split `dark_cat_3.ico` / `dark_cat_sleep_0.gif` into theme, state and
+/// frame number. Running frames have no state part.
+pub fn parse_frame_name(
+    name: &str,
+    file_name: &str,
+) -> Option<(&'static str, &'static str, usize)> {
+    let (stem, extension) = file_name.rsplit_once('.')?;
+    if !FRAME_EXTENSIONS.contains(&extension.to_ascii_lowercase().as_str()) {
+        return None;
+    }
+    let (theme, rest) = stem.split_once('_')?;
+    let theme = KNOWN_THEMES.into_iter().find(|t| *t == theme)?;
+    let rest = rest.strip_prefix(name)?.strip_prefix('_')?;
+    let (state, index) = match rest.rsplit_once('_') {
+        Some((state, index)) => (KNOWN_STATES.into_iter().find(|s| *s == state)?, index),
+        None => ("run", rest),
+    };
+    Some((theme, state, index.parse().ok()?))
+}
+
+/// Name of frame `index` next to `file`, frame `last` of the same group:
+/// `dark_cat_7.ico` and 2 make `dark_cat_2.ico`
+fn frame_name(file: &str, last: usize, index: usize) -> String {
+    let (stem, extension) = file.rsplit_once('.').unwrap_or((file, "ico"));
+    let prefix = stem.strip_suffix(&last.to_string()).unwrap_or(stem);
+    format!("{}{}.{}", prefix, index, extension)
+}
+
+/// `mascot` -> `Mascot`
+fn display_name(name: &str) -> String {
+    let mut chars = name.chars();
+    match chars.next() {
+        Some(first) => first.to_uppercase().collect::<String>() + chars.as_str(),
+        None => String::new(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_builtin_manifests_are_valid() {
+        for text in [
+            include_str!("../assets/cat/pack.toml"),
+            include_str!("../assets/parrot/pack.toml"),
+        ] {
+            let manifest = PackManifest::parse(text).unwrap();
+            assert_eq!(manifest.themes, ["light", "dark"]);
+        }
+    }
+
+    #[test]
+    fn test_parse_frame_name() {
+        assert_eq!(
+            parse_frame_name("cat", "dark_cat_3.ico"),
+            Some(("dark", "run", 3))
+        );
+        assert_eq!(
+            parse_frame_name("cat", "light_cat_sleep_0.ico"),
+            Some(("light", "sleep", 0))
+        );
+        assert_eq!(
+            parse_frame_name("cat", "dark_cat_0.gif"),
+            Some(("dark", "run", 0))
+        );
+        assert_eq!(parse_frame_name("cat", "dark_catfish_0.ico"), None);
+        assert_eq!(parse_frame_name("cat", "dark_cat_nap_0.ico"), None);
+        assert_eq!(parse_frame_name("cat", "dark_sleep1.png"), None);
+    }
+
+    #[test]
+    fn test_scan_reports_unmatched_frames() {
+        let dir = std::env::temp_dir().join(format!("rustcat-scan-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        for file in ["dark_bob_0.ico", "dark_bob_1.ico", "light_bob_0.ico"] {
+            std::fs::write(dir.join(file), b"").unwrap();
+        }
+        let error = PackManifest::scan("bob", &dir).unwrap_err();
+        assert!(error.contains("light_bob_1.ico"), "{}", error);
+
+        std::fs::write(dir.join("light_bob_1.ico"), b"").unwrap();
+        let manifest = PackManifest::scan("bob", &dir).unwrap();
+        assert_eq!(manifest.frame_files("run", "light").len(), 2);
+        assert!(manifest.missing_frames(&dir).is_empty());
+
+        std::fs::remove_file(dir.join("dark_bob_1.ico")).unwrap();
+        let manifest = PackManifest::parse(
+            "name = \"Bob\"\n[states.run]\nframes = [\"{theme}_bob_0.ico\", \"{theme}_bob_1.ico\"]",
+        )
+        .unwrap();
+        assert_eq!(manifest.missing_frames(&dir), ["dark_bob_1.ico"]);
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_scan_reports_gaps_and_duplicates() {
+        let dir = std::env::temp_dir().join(format!("rustcat-gaps-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        for file in [
+            "dark_bob_0.ico",
+            "dark_bob_1.ico",
+            "dark_bob_3.ico",
+            "light_bob_0.ico",
+            "light_bob_1.ico",
+            "light_bob_3.ico",
+            "dark_bob_sleep_1.png",
+            "light_bob_sleep_1.png",
+        ] {
+            std::fs::write(dir.join(file), b"").unwrap();
+        }
+        let error = PackManifest::scan("bob", &dir).unwrap_err();
+        assert_eq!(
+            error,
+            "missing frames dark_bob_2.ico, light_bob_2.ico, \
+             dark_bob_sleep_0.png, light_bob_sleep_0.png"
+        );
+
+        for theme in ["dark", "light"] {
+            std::fs::write(dir.join(format!("{}_bob_2.ico", theme)), b"").unwrap();
+            std::fs::write(dir.join(format!("{}_bob_sleep_0.png", theme)), b"").unwrap();
+        }
+        let manifest = PackManifest::scan("bob", &dir).unwrap();
+        assert_eq!(manifest.frame_files("run", "dark").len(), 4);
+        assert_eq!(manifest.frame_files("sleep", "dark").len(), 2);
+
+        std::fs::write(dir.join("dark_bob_0.png"), b"").unwrap();
+        let error = PackManifest::scan("bob", &dir).unwrap_err();
+        assert_eq!(error, "dark_bob_0.ico and dark_bob_0.png are both frame 0");
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_validation() {
+        let manifest = PackManifest::parse(
+            r#"
+            name = "Bob"
+            themes = ["dark"]
+            [states.run]
+            frames = ["{theme}_a.ico", "{theme}_b.ico"]
+            durations = [1.0, 2.5]
+            "#,
+        )
+        .unwrap();
+        assert_eq!(
+            manifest.frame_files("run", "dark"),
+            ["dark_a.ico", "dark_b.ico"]
+        );
+        assert_eq!(manifest.duration("run", 1), 2.5);
+        assert_eq!(manifest.duration("sleep", 0), 1.0);
+
+        // Mismatched and zero durations, no run state, unknown state, unknown theme
+        let invalid = [
+            "name = \"x\"\n[states.run]\nframes = [\"a\"]\ndurations = [1.0, 1.0]",
+            "name = \"x\"\n[states.run]\nframes = [\"a\"]\ndurations = [0.0]",
+            "name = \"x\"\n[states.sleep]\nframes = [\"a\"]",
+            "name = \"x\"\n[states.run]\nframes = [\"a\"]\n[states.dance]\nframes = [\"a\"]",
+            "name = \"x\"\nthemes = [\"sepia\"]\n[states.run]\nframes = [\"a\"]",
+        ];
+        for text in invalid {
+            assert!(PackManifest::parse(text).is_err(), "{}", text);
+        }
+    }
+}
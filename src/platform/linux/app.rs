@@ -1,5 +1,8 @@
-use crate::app::App;
-use crate::icon_manager::{IconManager, Theme};
+use super::{dbus, settings};
+use crate::app::{App, AppStatus, Overrides};
+use crate::events::Events;
+use crate::icon_manager::IconManager;
+use std::sync::mpsc;
 
 pub struct LinuxApp {
     app: App,
@@ -9,12 +12,28 @@ impl LinuxApp {
     pub fn new(
         icon_manager: IconManager,
         initial_icon: &str,
-        initial_theme: Option<Theme>,
+        overrides: Overrides,
+        show_tray: bool,
     ) -> Result<Self, Box<dyn std::error::Error>> {
-        let app = App::new(icon_manager, initial_icon, initial_theme)?;
+        let app = App::new(icon_manager, initial_icon, overrides, show_tray)?;
+
+        // Apply hand edits of settings.toml without a restart
+        let sender = app.event_sender();
+        settings::watch_settings(move || sender.send(Events::SettingsChanged).is_ok());
+        dbus::publish(app.event_sender(), app.status());
+
         Ok(LinuxApp { app })
     }
 
+    /// Sender for events from outside the tray menu
+    pub fn event_sender(&self) -> mpsc::Sender<Events> {
+        self.app.event_sender()
+    }
+
+    pub fn status(&self) -> AppStatus {
+        self.app.status()
+    }
+
     pub fn start_animation_thread(&self) {
         self.app.start_animation_thread();
     }
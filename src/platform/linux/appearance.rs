@@ -0,0 +1,207 @@
+//! Live light/dark preference of the desktop, behind `Theme::Auto`.
+//!
+//! The preference comes from `org.freedesktop.appearance color-scheme` of
+//! the XDG desktop portal, kept current through its `SettingChanged`
+//! signal. Without a portal, KDE's `kdeglobals` is polled for changes.
+
+use crate::debug;
+use crate::platform::linux::LinuxSettingsManager;
+use crate::platform::SettingsManager;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Once;
+use std::thread;
+use std::time::{Duration, SystemTime};
+use zbus::blocking::{Connection, Proxy};
+use zbus::zvariant::{OwnedValue, Value};
+
+const PORTAL_DESTINATION: &str = "org.freedesktop.portal.Desktop";
+const PORTAL_PATH: &str = "/org/freedesktop/portal/desktop";
+const SETTINGS_INTERFACE: &str = "org.freedesktop.portal.Settings";
+const APPEARANCE_NAMESPACE: &str = "org.freedesktop.appearance";
+const COLOR_SCHEME_KEY: &str = "color-scheme";
+
+/// How often `kdeglobals` is checked when there is no portal
+const KDEGLOBALS_POLL_INTERVAL: Duration = Duration::from_secs(2);
+
+static PREFERS_DARK: AtomicBool = AtomicBool::new(false);
+static WATCHER: Once = Once::new();
+
+/// Whether the desktop currently prefers dark. The first call reads the
+/// preference and starts following it in the background.
+pub fn prefers_dark() -> bool {
+    WATCHER.call_once(start_watching);
+    PREFERS_DARK.load(Ordering::Relaxed)
+}
+
+fn start_watching() {
+    let portal = Connection::session().and_then(|connection| {
+        let scheme = read_color_scheme(&connection)?;
+        Ok((connection, scheme))
+    });
+    match portal {
+        Ok((connection, scheme)) => {
+            set_color_scheme(scheme);
+            thread::spawn(move || {
+                match color_scheme_changes(&connection) {
+                    Ok(changes) => changes.for_each(set_color_scheme),
+                    Err(e) => eprintln!("Failed to subscribe to the settings portal: {}", e),
+                }
+                // The portal went away, keep following KDE at least
+                watch_kdeglobals();
+            });
+        }
+        Err(e) => {
+            debug!("Settings portal unavailable ({}), watching kdeglobals", e);
+            PREFERS_DARK.store(
+                LinuxSettingsManager::is_dark_mode_enabled(),
+                Ordering::Relaxed,
+            );
+            thread::spawn(watch_kdeglobals);
+        }
+    }
+}
+
+/// `color-scheme` is 0 for no preference, 1 for dark and 2 for light
+fn set_color_scheme(scheme: u32) {
+    let dark = match scheme {
+        1 => true,
+        2 => false,
+        _ => LinuxSettingsManager::is_dark_mode_enabled(),
+    };
+    debug!("color-scheme is {}, dark: {}", scheme, dark);
+    PREFERS_DARK.store(dark, Ordering::Relaxed);
+}
+
+fn settings_proxy(connection: &Connection) -> zbus::Result<Proxy<'static>> {
+    Proxy::new(
+        connection,
+        PORTAL_DESTINATION,
+        PORTAL_PATH,
+        SETTINGS_INTERFACE,
+    )
+}
+
+/// Current `color-scheme` from the portal
+fn read_color_scheme(connection: &Connection) -> zbus::Result<u32> {
+    let proxy = settings_proxy(connection)?;
+    // `ReadOne` is version 2 of the interface, `Read` wraps the value twice
+    let value: OwnedValue = proxy
+        .call("ReadOne", &(APPEARANCE_NAMESPACE, COLOR_SCHEME_KEY))
+        .or_else(|_| proxy.call("Read", &(APPEARANCE_NAMESPACE, COLOR_SCHEME_KEY)))?;
+    color_scheme_value(&value)
+        .ok_or_else(|| zbus::Error::Failure(format!("unexpected color-scheme {:?}", value)))
+}
+
+/// New `color-scheme` values, as announced by `SettingChanged`. Blocks
+/// between changes and ends when the connection closes.
+fn color_scheme_changes(
+    connection: &Connection,
+) -> zbus::Result<impl Iterator<Item = u32> + 'static> {
+    let signals = settings_proxy(connection)?.receive_signal("SettingChanged")?;
+    Ok(signals.filter_map(|message| {
+        let (namespace, key, value): (String, String, OwnedValue) =
+            message.body().deserialize().ok()?;
+        if namespace != APPEARANCE_NAMESPACE || key != COLOR_SCHEME_KEY {
+            return None;
+        }
+        color_scheme_value(&value)
+    }))
+}
+
+fn color_scheme_value(value: &Value) -> Option<u32> {
+    match value {
+        Value::U32(scheme) => Some(*scheme),
+        Value::Value(inner) => color_scheme_value(inner),
+        _ => None,
+    }
+}
+
+/// Re-read the KDE color scheme whenever `kdeglobals` changes
+fn watch_kdeglobals() {
+    let Some(path) = dirs::config_dir().map(|dir| dir.join("kdeglobals")) else {
+        return;
+    };
+    let modified = |path: &std::path::Path| -> Option<SystemTime> {
+        std::fs::metadata(path).and_then(|m| m.modified()).ok()
+    };
+    let mut last_modified = modified(&path);
+    loop {
+        thread::sleep(KDEGLOBALS_POLL_INTERVAL);
+        let current = modified(&path);
+        if current != last_modified {
+            last_modified = current;
+            let dark = LinuxSettingsManager::is_dark_mode_enabled();
+            debug!("kdeglobals changed, dark: {}", dark);
+            PREFERS_DARK.store(dark, Ordering::Relaxed);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::platform::linux::test_bus::TestBus;
+    use std::sync::mpsc;
+
+    struct MockSettings {
+        color_scheme: u32,
+    }
+
+    #[zbus::interface(name = "org.freedesktop.portal.Settings")]
+    impl MockSettings {
+        fn read_one(&self, namespace: &str, key: &str) -> zbus::fdo::Result<OwnedValue> {
+            if namespace == APPEARANCE_NAMESPACE && key == COLOR_SCHEME_KEY {
+                Ok(OwnedValue::from(self.color_scheme))
+            } else {
+                Err(zbus::fdo::Error::Failed("no such setting".to_string()))
+            }
+        }
+    }
+
+    #[test]
+    fn test_mock_portal() {
+        let Some(bus) = TestBus::start() else {
+            eprintln!("dbus-daemon not found, skipping");
+            return;
+        };
+        let portal = zbus::blocking::connection::Builder::address(bus.address())
+            .unwrap()
+            .name(PORTAL_DESTINATION)
+            .unwrap()
+            .serve_at(PORTAL_PATH, MockSettings { color_scheme: 1 })
+            .unwrap()
+            .build()
+            .unwrap();
+
+        let client = zbus::blocking::connection::Builder::address(bus.address())
+            .unwrap()
+            .build()
+            .unwrap();
+        assert_eq!(read_color_scheme(&client).unwrap(), 1);
+
+        let changes = color_scheme_changes(&client).unwrap();
+        let (sender, receiver) = mpsc::channel();
+        thread::spawn(move || {
+            for scheme in changes {
+                if sender.send(scheme).is_err() {
+                    break;
+                }
+            }
+        });
+        let emit = |namespace: &str, key: &str, scheme: u32| {
+            portal
+                .emit_signal(
+                    None::<()>,
+                    PORTAL_PATH,
+                    SETTINGS_INTERFACE,
+                    "SettingChanged",
+                    &(namespace, key, Value::from(scheme)),
+                )
+                .unwrap();
+        };
+        // Other settings are ignored
+        emit("org.kde.kdeglobals.General", "ColorScheme", 1);
+        emit(APPEARANCE_NAMESPACE, COLOR_SCHEME_KEY, 2);
+        assert_eq!(receiver.recv_timeout(Duration::from_secs(5)), Ok(2));
+    }
+}
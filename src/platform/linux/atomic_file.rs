@@ -0,0 +1,127 @@
+//! Crash-safe file replacement for the settings file.
+//!
+//! A new version is written to a temporary file next to the target, synced
+//! to disk and renamed over the target, so readers and a crash at any point
+//! see either the old or the new file, never a truncated one. Writers
+//! serialize on an advisory lock held on a separate `.lock` file, since the
+//! target itself is replaced on every write.
+
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+
+/// Exclusive advisory lock on `.<name>.lock` next to `path`, released when
+/// dropped. Blocks while another writer, in this process or another, holds
+/// it.
+pub fn lock(path: &Path) -> io::Result<File> {
+    let lock_file = OpenOptions::new()
+        .create(true)
+        .truncate(false)
+        .write(true)
+        .open(sidecar(path, "lock"))?;
+    lock_file.lock()?;
+    Ok(lock_file)
+}
+
+/// Replace `path` with `contents`.
+pub fn write(path: &Path, contents: &[u8]) -> io::Result<()> {
+    write_with(path, |file| file.write_all(contents))
+}
+
+/// Replace `path` with whatever `fill` writes. If `fill` fails the target is
+/// left untouched.
+fn write_with(path: &Path, fill: impl FnOnce(&mut File) -> io::Result<()>) -> io::Result<()> {
+    let temp = sidecar(path, &format!("tmp-{}", std::process::id()));
+    let result = (|| {
+        let mut file = File::create(&temp)?;
+        fill(&mut file)?;
+        file.sync_all()?;
+        fs::rename(&temp, path)
+    })();
+    if result.is_err() {
+        let _ = fs::remove_file(&temp);
+        return result;
+    }
+    // Make the rename itself durable
+    if let Some(dir) = path.parent() {
+        File::open(dir)?.sync_all()?;
+    }
+    Ok(())
+}
+
+/// `dir/.name.suffix`, hidden next to `path`
+fn sidecar(path: &Path, suffix: &str) -> PathBuf {
+    let name = path.file_name().unwrap_or_default().to_string_lossy();
+    path.with_file_name(format!(".{}.{}", name, suffix))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::thread;
+
+    fn temp_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("rustcat-{}-{}", name, std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    #[test]
+    fn test_interrupted_write_keeps_old_file() {
+        let dir = temp_dir("atomic");
+        let path = dir.join("settings.toml");
+        write(&path, b"icon = \"cat\"\n").unwrap();
+
+        // Crash halfway through writing the new version
+        let error = write_with(&path, |file| {
+            file.write_all(b"icon = \"par")?;
+            Err(io::Error::other("killed"))
+        })
+        .unwrap_err();
+        assert_eq!(error.to_string(), "killed");
+        assert_eq!(fs::read_to_string(&path).unwrap(), "icon = \"cat\"\n");
+
+        // A temp file left behind by a crashed process is simply replaced
+        fs::write(
+            sidecar(&path, &format!("tmp-{}", std::process::id())),
+            "garb",
+        )
+        .unwrap();
+        write(&path, b"icon = \"parrot\"\n").unwrap();
+        assert_eq!(fs::read_to_string(&path).unwrap(), "icon = \"parrot\"\n");
+
+        let mut leftovers: Vec<_> = fs::read_dir(&dir)
+            .unwrap()
+            .map(|entry| entry.unwrap().file_name().into_string().unwrap())
+            .collect();
+        leftovers.sort();
+        assert_eq!(leftovers, ["settings.toml"]);
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_lock_serializes_writers() {
+        let dir = temp_dir("lock");
+        let path = dir.join("counter");
+        write(&path, b"0").unwrap();
+
+        let writers: Vec<_> = (0..4)
+            .map(|_| {
+                let path = path.clone();
+                thread::spawn(move || {
+                    for _ in 0..25 {
+                        let _guard = lock(&path).unwrap();
+                        let n: u32 = fs::read_to_string(&path).unwrap().parse().unwrap();
+                        // Temp names are per process, so threads must not overlap
+                        write(&path, (n + 1).to_string().as_bytes()).unwrap();
+                    }
+                })
+            })
+            .collect();
+        for writer in writers {
+            writer.join().unwrap();
+        }
+        assert_eq!(fs::read_to_string(&path).unwrap(), "100");
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,208 @@
+//! Control protocol spoken on `$XDG_RUNTIME_DIR/rustcat.sock`.
+//!
+//! Scripts drive the running cat through the socket the tray instance
+//! listens on (see [`super::instance`]), for example with
+//! `echo "icon parrot" | socat - UNIX-CONNECT:$XDG_RUNTIME_DIR/rustcat.sock`.
+//! Every request is one line and gets one line back: `ok`, `ok <value>` for
+//! queries, or `error: <reason>`.
+//!
+//! Commands, fed into the same event channel as the tray menu:
+//!
+//! - `icon <name>`, `theme <dark|light|auto>`, `metric <name>`,
+//!   `curve <linear|logarithmic|exponential|stepped|custom>`: switch and
+//!   save, like the menu does
+//! - `sleep <auto|asleep|awake>`: override idle detection until changed back
+//! - `run-on-start <on|off>`
+//! - `reload`: re-read the stored settings
+//! - `about`, `quit`
+//!
+//! Queries, answered from the app's current state:
+//!
+//! - `get <icon|theme|metric|usage|state|sleep|tooltip>`
+//! - `status`: all of the above but the tooltip as `key=value` pairs
+//! - `help`: the request names
+
+use crate::app::AppStatus;
+use crate::events::Events;
+use crate::icon_manager::Theme;
+use crate::platform::{SettingsManager, SettingsManagerImpl};
+use crate::schedule::SleepOverride;
+use crate::speed_curve::CurveKind;
+use std::io::{self, BufRead, BufReader, Write};
+use std::os::unix::net::UnixStream;
+use std::sync::mpsc::Sender;
+
+const REQUESTS: &str =
+    "icon theme metric curve sleep run-on-start reload about quit get status help";
+
+/// Answer requests on `stream` until the client hangs up
+pub fn handle(stream: UnixStream, events: &Sender<Events>, status: &AppStatus) -> io::Result<()> {
+    let mut writer = stream.try_clone()?;
+    for line in BufReader::new(stream).lines() {
+        let reply = match respond(&line?, status) {
+            Ok(Reply::Event(event)) => {
+                if events.send(event).is_err() {
+                    // Shutting down
+                    return Ok(());
+                }
+                "ok".to_string()
+            }
+            Ok(Reply::Value(value)) => format!("ok {}", value),
+            Err(e) => format!("error: {}", e),
+        };
+        writeln!(writer, "{}", reply)?;
+    }
+    Ok(())
+}
+
+#[derive(Debug, PartialEq)]
+enum Reply {
+    Event(Events),
+    Value(String),
+}
+
+fn respond(line: &str, status: &AppStatus) -> Result<Reply, String> {
+    let line = line.trim();
+    let (request, argument) = line
+        .split_once(' ')
+        .map_or((line, ""), |(request, argument)| (request, argument.trim()));
+    let event = match (request, argument) {
+        ("icon", name) => {
+            if !status
+                .icon_manager()
+                .available_icons()
+                .iter()
+                .any(|icon| icon == name)
+            {
+                return Err(format!("unknown icon '{}'", name));
+            }
+            Events::SetIcon(name.to_string())
+        }
+        ("theme", name) => {
+            Events::SetTheme(Theme::from_name(name).ok_or(format!("unknown theme '{}'", name))?)
+        }
+        ("metric", name) => {
+            if !status.metric_names().iter().any(|metric| metric == name) {
+                return Err(format!("unknown metric '{}'", name));
+            }
+            Events::SetMetric(name.to_string())
+        }
+        ("curve", name) => Events::SetSpeedCurve(
+            CurveKind::from_name(name).ok_or(format!("unknown curve '{}'", name))?,
+        ),
+        ("sleep", name) => Events::SetSleepOverride(
+            SleepOverride::from_name(name).ok_or(format!("unknown sleep mode '{}'", name))?,
+        ),
+        ("run-on-start", value) => {
+            let enable = match value {
+                "on" => true,
+                "off" => false,
+                _ => return Err(format!("expected on or off, got '{}'", value)),
+            };
+            if SettingsManagerImpl::is_run_on_start_enabled() == enable {
+                return Ok(Reply::Value(value.to_string()));
+            }
+            Events::ToggleRunOnStart
+        }
+        ("reload", "") => Events::SettingsChanged,
+        ("about", "") => Events::ShowAboutDialog,
+        ("quit", "") => Events::Exit,
+        ("get", key) => return query(key, status).map(Reply::Value),
+        ("status", "") => {
+            let keys = ["icon", "theme", "metric", "usage", "state", "sleep"];
+            let pairs: Vec<String> = keys
+                .iter()
+                .map(|key| format!("{}={}", key, query(key, status).unwrap_or_default()))
+                .collect();
+            return Ok(Reply::Value(pairs.join(" ")));
+        }
+        ("help", "") => return Ok(Reply::Value(REQUESTS.to_string())),
+        _ => return Err(format!("unknown request '{}'", line)),
+    };
+    Ok(Reply::Event(event))
+}
+
+fn query(key: &str, status: &AppStatus) -> Result<String, String> {
+    Ok(match key {
+        "icon" => status.icon(),
+        "theme" => status.theme().to_string(),
+        "metric" => status.metric(),
+        "usage" => format!("{:.2}", status.activity().usage),
+        "state" => status.activity().state.to_string(),
+        "sleep" => status.sleep_override().name().to_string(),
+        "tooltip" => status.activity().tooltip,
+        _ => return Err(format!("unknown query '{}'", key)),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::net::Shutdown;
+    use std::sync::mpsc;
+    use std::thread;
+
+    /// Send `requests` over a socket, returning the replies and the events
+    fn exchange(requests: &[&str]) -> (Vec<String>, Vec<Events>) {
+        let (mut client, server) = UnixStream::pair().unwrap();
+        let (sender, receiver) = mpsc::channel();
+        let server = thread::spawn(move || handle(server, &sender, &AppStatus::for_tests()));
+        for request in requests {
+            writeln!(client, "{}", request).unwrap();
+        }
+        client.shutdown(Shutdown::Write).unwrap();
+        let replies = BufReader::new(client).lines().map(Result::unwrap).collect();
+        server.join().unwrap().unwrap();
+        (replies, receiver.try_iter().collect())
+    }
+
+    #[test]
+    fn test_commands() {
+        let (replies, events) = exchange(&[
+            "icon parrot",
+            "icon dog",
+            " sleep awake ",
+            "metric memory",
+            "curve stepped",
+            "quit",
+            "feed cat",
+        ]);
+        assert_eq!(
+            replies,
+            [
+                "ok",
+                "error: unknown icon 'dog'",
+                "ok",
+                "ok",
+                "ok",
+                "ok",
+                "error: unknown request 'feed cat'"
+            ]
+        );
+        assert_eq!(
+            events,
+            [
+                Events::SetIcon("parrot".to_string()),
+                Events::SetSleepOverride(SleepOverride::ForceWake),
+                Events::SetMetric("memory".to_string()),
+                Events::SetSpeedCurve(CurveKind::Stepped),
+                Events::Exit,
+            ]
+        );
+    }
+
+    #[test]
+    fn test_queries() {
+        let (replies, events) = exchange(&["get usage", "get state", "get mood", "status"]);
+        assert_eq!(
+            replies,
+            [
+                "ok 42.00",
+                "ok run",
+                "error: unknown query 'mood'",
+                "ok icon=cat theme=dark metric=cpu usage=42.00 state=run sleep=auto"
+            ]
+        );
+        assert!(events.is_empty());
+    }
+}
@@ -1,54 +1,199 @@
-use crate::platform::CpuMonitor;
+use crate::platform::{MetricSource, Reading};
+use std::collections::BTreeMap;
 use std::fs;
 use std::io;
-use std::sync::Mutex;
 
-pub struct LinuxCpuMonitor;
+/// `(total, idle)` tick counters from a single /proc/stat line.
+type CpuTicks = (f64, f64);
 
-static CPU_STATE: Mutex<Option<(f64, f64)>> = Mutex::new(None);
+/// CPU usage from /proc/stat, either averaged over all cores or taken from
+/// the busiest one. On many-core machines a single pegged core barely moves
+/// the average, so the busiest core is offered as a separate source.
+pub struct LinuxCpuMonitor {
+    busiest_core: bool,
+    prev: Option<CpuTicks>,
+    // Previous sample per online core, keyed by the N in `cpuN`. Cores that go
+    // offline disappear from /proc/stat and are dropped from here, so a core
+    // that comes back starts over from a fresh baseline instead of diffing
+    // against counters from before it went away.
+    prev_cores: BTreeMap<usize, CpuTicks>,
+    average: f64,
+    busiest: Option<f64>,
+}
 
-impl CpuMonitor for LinuxCpuMonitor {
-    fn get_cpu_usage() -> io::Result<f64> {
-        // /proc/stat first line (aggregate over all CPUs):
+impl LinuxCpuMonitor {
+    pub fn average() -> Self {
+        Self::new(false)
+    }
+
+    pub fn busiest_core() -> Self {
+        Self::new(true)
+    }
+
+    fn new(busiest_core: bool) -> Self {
+        Self {
+            busiest_core,
+            prev: None,
+            prev_cores: BTreeMap::new(),
+            average: 0.0,
+            busiest: None,
+        }
+    }
+}
+
+impl MetricSource for LinuxCpuMonitor {
+    fn name(&self) -> &str {
+        if self.busiest_core {
+            "cpu-busiest"
+        } else {
+            "cpu"
+        }
+    }
+
+    fn label(&self) -> &str {
+        if self.busiest_core {
+            "CPU (Busiest Core)"
+        } else {
+            "CPU"
+        }
+    }
+
+    fn sample(&mut self) -> io::Result<Reading> {
+        // /proc/stat first line (aggregate over all CPUs), then one per core:
         //   cpu  user nice system idle iowait irq softirq steal guest guest_nice
         let contents = fs::read_to_string("/proc/stat")?;
-        let first_line = contents
-            .lines()
-            .next()
-            .ok_or_else(|| io::Error::other("Empty /proc/stat"))?;
-
-        let fields: Vec<&str> = first_line.split_whitespace().collect();
-        if fields.is_empty() || fields[0] != "cpu" {
-            return Err(io::Error::other("Unexpected /proc/stat format"));
+        let (total, cores) = parse_proc_stat(&contents)?;
+
+        self.average = match self.prev {
+            Some(prev) => usage_between(prev, total),
+            None => 0.0, // First call, return 0 usage
+        };
+        self.prev = Some(total);
+        self.busiest = per_core_usage(&mut self.prev_cores, cores)
+            .into_iter()
+            .flatten()
+            .reduce(f64::max);
+
+        let value = match self.busiest {
+            Some(busiest) if self.busiest_core => busiest,
+            _ => self.average,
+        };
+        Ok(Reading { value, raw: value })
+    }
+
+    fn describe(&self, _reading: &Reading) -> String {
+        match self.busiest {
+            Some(busiest) => format!(
+                "CPU Usage: {:.2}% (busiest core: {:.2}%)",
+                self.average, busiest
+            ),
+            None => format!("CPU Usage: {:.2}%", self.average),
         }
+    }
+}
 
-        // Parse the time fields (user, nice, system, idle, iowait, irq, softirq, steal, ...)
-        let ticks: Vec<f64> = fields[1..]
-            .iter()
-            .map(|f| f.parse::<f64>().unwrap_or(0.0))
-            .collect();
-
-        // idle = idle + iowait (indices 3 and 4)
-        let idle = ticks.get(3).copied().unwrap_or(0.0)
-            + ticks.get(4).copied().unwrap_or(0.0);
-        let total: f64 = ticks.iter().sum();
-
-        let mut state = CPU_STATE.lock().unwrap();
-        let usage = if let Some((prev_total, prev_idle)) = *state {
-            let total_diff = total - prev_total;
-            let idle_diff = idle - prev_idle;
-            if total_diff > 0.0 {
-                // Clamp to [0, 100] — /proc counters can be non-monotonic on
-                // VMs / after suspend, yielding spurious negative or >100 values.
-                (100.0 - (idle_diff / total_diff * 100.0)).clamp(0.0, 100.0)
-            } else {
-                0.0
-            }
-        } else {
-            0.0 // First call, return 0 usage
+/// Parse /proc/stat into the aggregate `cpu` counters and the `cpuN` counters
+/// of every core that is currently online.
+fn parse_proc_stat(contents: &str) -> io::Result<(CpuTicks, BTreeMap<usize, CpuTicks>)> {
+    let mut lines = contents.lines();
+    let first_line = lines
+        .next()
+        .ok_or_else(|| io::Error::other("Empty /proc/stat"))?;
+
+    let fields: Vec<&str> = first_line.split_whitespace().collect();
+    if fields.is_empty() || fields[0] != "cpu" {
+        return Err(io::Error::other("Unexpected /proc/stat format"));
+    }
+    let total = parse_ticks(&fields[1..]);
+
+    // Per-core lines follow the aggregate one; offline cores are simply
+    // missing, so the indices are not necessarily contiguous.
+    let mut cores = BTreeMap::new();
+    for line in lines {
+        let fields: Vec<&str> = line.split_whitespace().collect();
+        let Some(index) = fields
+            .first()
+            .and_then(|name| name.strip_prefix("cpu"))
+            .and_then(|n| n.parse::<usize>().ok())
+        else {
+            break;
         };
+        cores.insert(index, parse_ticks(&fields[1..]));
+    }
+
+    Ok((total, cores))
+}
+
+/// Sum the time fields (user, nice, system, idle, iowait, irq, softirq, steal, ...)
+/// of one /proc/stat line into `(total, idle)`.
+fn parse_ticks(fields: &[&str]) -> CpuTicks {
+    let ticks: Vec<f64> = fields
+        .iter()
+        .map(|f| f.parse::<f64>().unwrap_or(0.0))
+        .collect();
+
+    // idle = idle + iowait (indices 3 and 4)
+    let idle = ticks.get(3).copied().unwrap_or(0.0) + ticks.get(4).copied().unwrap_or(0.0);
+    let total: f64 = ticks.iter().sum();
+    (total, idle)
+}
+
+fn usage_between((prev_total, prev_idle): CpuTicks, (total, idle): CpuTicks) -> f64 {
+    let total_diff = total - prev_total;
+    let idle_diff = idle - prev_idle;
+    if total_diff > 0.0 {
+        // Clamp to [0, 100] — /proc counters can be non-monotonic on
+        // VMs / after suspend, yielding spurious negative or >100 values.
+        (100.0 - (idle_diff / total_diff * 100.0)).clamp(0.0, 100.0)
+    } else {
+        0.0
+    }
+}
+
+/// Diff the current per-core counters against `state` and replace `state`
+/// with them. The result is indexed by core number; offline cores and cores
+/// seen for the first time report `None`.
+fn per_core_usage(
+    state: &mut BTreeMap<usize, CpuTicks>,
+    cores: BTreeMap<usize, CpuTicks>,
+) -> Vec<Option<f64>> {
+    let len = cores.keys().next_back().map_or(0, |&max| max + 1);
+    let mut usage = vec![None; len];
+    for (&index, &ticks) in &cores {
+        usage[index] = state.get(&index).map(|&prev| usage_between(prev, ticks));
+    }
+    *state = cores;
+    usage
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_proc_stat_skips_offline_cores() {
+        let stat = "cpu  40 0 40 100 20 0 0 0 0 0\n\
+                    cpu0 20 0 20 50 10 0 0 0 0 0\n\
+                    cpu2 20 0 20 50 10 0 0 0 0 0\n\
+                    intr 12345 0 0\n";
+        let (total, cores) = parse_proc_stat(stat).unwrap();
+        assert_eq!(total, (200.0, 120.0));
+        assert_eq!(cores.keys().copied().collect::<Vec<_>>(), vec![0, 2]);
+        assert_eq!(cores[&2], (100.0, 60.0));
+    }
+
+    #[test]
+    fn test_per_core_usage_handles_cores_going_offline() {
+        let mut state = BTreeMap::new();
+        let first = BTreeMap::from([(0, (100.0, 50.0)), (1, (100.0, 50.0))]);
+        assert_eq!(per_core_usage(&mut state, first), vec![None, None]);
+
+        // Core 1 goes offline, core 0 is fully busy.
+        let second = BTreeMap::from([(0, (200.0, 50.0))]);
+        assert_eq!(per_core_usage(&mut state, second), vec![Some(100.0)]);
 
-        *state = Some((total, idle));
-        Ok(usage)
+        // Core 1 comes back with fresh counters and needs a new baseline.
+        let third = BTreeMap::from([(0, (300.0, 100.0)), (1, (10.0, 5.0))]);
+        assert_eq!(per_core_usage(&mut state, third), vec![Some(50.0), None]);
     }
-}
\ No newline at end of file
+}
@@ -0,0 +1,261 @@
+//! `io.github.bearice.RustCat` on the session bus.
+//!
+//! Lets desktop shortcuts and scripts drive the running cat, for example a
+//! kglobalaccel binding to
+//! `qdbus io.github.bearice.RustCat /io/github/bearice/RustCat Pause`.
+//! The properties follow the app's state and announce changes through the
+//! standard `PropertiesChanged` signal. Commands go through the same event
+//! channel as the tray menu.
+
+use crate::app::AppStatus;
+use crate::debug;
+use crate::events::Events;
+use crate::icon_manager::Theme;
+use crate::schedule::SleepOverride;
+use std::collections::HashMap;
+use std::sync::mpsc::Sender;
+use std::thread;
+use std::time::Duration;
+use zbus::blocking::Connection;
+use zbus::fdo::{self, RequestNameFlags, RequestNameReply};
+use zbus::zvariant::Value;
+
+const SERVICE_NAME: &str = "io.github.bearice.RustCat";
+const OBJECT_PATH: &str = "/io/github/bearice/RustCat";
+const INTERFACE_NAME: &str = "io.github.bearice.RustCat";
+
+/// How often the properties are checked for changes
+const POLL_INTERVAL: Duration = Duration::from_millis(500);
+
+struct RustCat {
+    events: Sender<Events>,
+    status: AppStatus,
+}
+
+impl RustCat {
+    fn send(&self, event: Events) -> fdo::Result<()> {
+        self.events
+            .send(event)
+            .map_err(|_| fdo::Error::Failed("RustCat is shutting down".to_string()))
+    }
+}
+
+#[zbus::interface(name = "io.github.bearice.RustCat")]
+impl RustCat {
+    /// Switch to an icon pack and save it, like the Icon menu
+    fn set_icon(&self, name: &str) -> fdo::Result<()> {
+        if !self
+            .status
+            .icon_manager()
+            .available_icons()
+            .iter()
+            .any(|icon| icon == name)
+        {
+            return Err(fdo::Error::InvalidArgs(format!("unknown icon '{}'", name)));
+        }
+        self.send(Events::SetIcon(name.to_string()))
+    }
+
+    /// Switch to `dark`, `light` or `auto` and save it, like the Theme menu
+    fn set_theme(&self, name: &str) -> fdo::Result<()> {
+        let theme = Theme::from_name(name)
+            .ok_or_else(|| fdo::Error::InvalidArgs(format!("unknown theme '{}'", name)))?;
+        self.send(Events::SetTheme(theme))
+    }
+
+    /// Put the cat to sleep until `Resume`
+    fn pause(&self) -> fdo::Result<()> {
+        self.send(Events::SetSleepOverride(SleepOverride::ForceSleep))
+    }
+
+    /// Let idle detection decide again
+    fn resume(&self) -> fdo::Result<()> {
+        self.send(Events::SetSleepOverride(SleepOverride::Automatic))
+    }
+
+    fn quit(&self) -> fdo::Result<()> {
+        self.send(Events::Exit)
+    }
+
+    /// Smoothed value of the active metric, 0–100
+    #[zbus(property)]
+    fn current_usage(&self) -> f64 {
+        self.status.activity().usage
+    }
+
+    /// `run`, `sleep` or `alert`
+    #[zbus(property)]
+    fn state(&self) -> String {
+        self.status.activity().state.to_string()
+    }
+
+    #[zbus(property)]
+    fn icon_name(&self) -> String {
+        self.status.icon()
+    }
+}
+
+/// Property values, to tell which ones changed
+struct Properties {
+    current_usage: f64,
+    state: String,
+    icon_name: String,
+}
+
+impl Properties {
+    fn of(status: &AppStatus) -> Self {
+        let activity = status.activity();
+        Properties {
+            current_usage: activity.usage,
+            state: activity.state.to_string(),
+            icon_name: status.icon(),
+        }
+    }
+
+    /// Properties that differ in `other`, with their new values
+    fn changes(&self, other: &Properties) -> HashMap<&'static str, Value<'static>> {
+        let mut changes = HashMap::new();
+        if self.current_usage != other.current_usage {
+            changes.insert("CurrentUsage", Value::from(other.current_usage));
+        }
+        if self.state != other.state {
+            changes.insert("State", Value::from(other.state.clone()));
+        }
+        if self.icon_name != other.icon_name {
+            changes.insert("IconName", Value::from(other.icon_name.clone()));
+        }
+        changes
+    }
+}
+
+/// Publish the interface on the session bus. Failing to is not fatal: the
+/// tray and the control socket work without it.
+pub fn publish(events: Sender<Events>, status: AppStatus) {
+    let result = Connection::session().and_then(|connection| serve(&connection, events, status));
+    match result {
+        Ok(()) => {}
+        // Another cat that the instance lock couldn't see
+        Err(zbus::Error::NameTaken) => debug!("{} is already taken", SERVICE_NAME),
+        Err(e) => eprintln!("Failed to publish {} on D-Bus: {}", SERVICE_NAME, e),
+    }
+}
+
+fn serve(connection: &Connection, events: Sender<Events>, status: AppStatus) -> zbus::Result<()> {
+    connection.object_server().at(
+        OBJECT_PATH,
+        RustCat {
+            events,
+            status: status.clone(),
+        },
+    )?;
+    match connection.request_name_with_flags(SERVICE_NAME, RequestNameFlags::DoNotQueue.into())? {
+        RequestNameReply::PrimaryOwner | RequestNameReply::AlreadyOwner => {}
+        RequestNameReply::Exists | RequestNameReply::InQueue => return Err(zbus::Error::NameTaken),
+    }
+
+    let connection = connection.clone();
+    thread::spawn(move || {
+        let mut last = Properties::of(&status);
+        loop {
+            thread::sleep(POLL_INTERVAL);
+            let current = Properties::of(&status);
+            let changes = last.changes(&current);
+            if !changes.is_empty() {
+                let result = connection.emit_signal(
+                    None::<()>,
+                    OBJECT_PATH,
+                    "org.freedesktop.DBus.Properties",
+                    "PropertiesChanged",
+                    &(INTERFACE_NAME, changes, Vec::<String>::new()),
+                );
+                if let Err(e) = result {
+                    debug!("Stopped announcing property changes: {}", e);
+                    return;
+                }
+            }
+            last = current;
+        }
+    });
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::app::Activity;
+    use crate::icon_manager::AnimationState;
+    use crate::platform::linux::test_bus::TestBus;
+    use std::sync::mpsc;
+    use zbus::blocking::Proxy;
+    use zbus::zvariant::OwnedValue;
+
+    #[test]
+    fn test_private_bus() {
+        let Some(bus) = TestBus::start() else {
+            eprintln!("dbus-daemon not found, skipping");
+            return;
+        };
+        let connect = || {
+            zbus::blocking::connection::Builder::address(bus.address())
+                .unwrap()
+                .build()
+                .unwrap()
+        };
+
+        let server = connect();
+        let status = AppStatus::for_tests();
+        let (sender, receiver) = mpsc::channel();
+        serve(&server, sender.clone(), status.clone()).unwrap();
+        // A second instance leaves the name to the first
+        assert!(matches!(
+            serve(&connect(), sender, status.clone()),
+            Err(zbus::Error::NameTaken)
+        ));
+
+        let client = connect();
+        let proxy = Proxy::new(&client, SERVICE_NAME, OBJECT_PATH, INTERFACE_NAME).unwrap();
+        proxy.call::<_, _, ()>("SetIcon", &("parrot",)).unwrap();
+        assert_eq!(
+            receiver.recv().unwrap(),
+            Events::SetIcon("parrot".to_string())
+        );
+        assert!(proxy.call::<_, _, ()>("SetIcon", &("dog",)).is_err());
+        proxy.call::<_, _, ()>("Pause", &()).unwrap();
+        assert_eq!(
+            receiver.recv().unwrap(),
+            Events::SetSleepOverride(SleepOverride::ForceSleep)
+        );
+        assert_eq!(proxy.get_property::<String>("IconName").unwrap(), "cat");
+        assert_eq!(proxy.get_property::<f64>("CurrentUsage").unwrap(), 42.0);
+
+        let changes = Proxy::new(
+            &client,
+            SERVICE_NAME,
+            OBJECT_PATH,
+            "org.freedesktop.DBus.Properties",
+        )
+        .unwrap()
+        .receive_signal("PropertiesChanged")
+        .unwrap();
+        let (sender, receiver) = mpsc::channel();
+        thread::spawn(move || {
+            for message in changes {
+                let body: (String, HashMap<String, OwnedValue>, Vec<String>) =
+                    message.body().deserialize().unwrap();
+                if sender.send(body).is_err() {
+                    break;
+                }
+            }
+        });
+        status.set_activity(Activity {
+            usage: 95.0,
+            state: AnimationState::Alert,
+            tooltip: String::new(),
+        });
+        let (interface, changed, _) = receiver.recv_timeout(Duration::from_secs(5)).unwrap();
+        assert_eq!(interface, INTERFACE_NAME);
+        assert_eq!(f64::try_from(&changed["CurrentUsage"]).unwrap(), 95.0);
+        assert_eq!(<&str>::try_from(&changed["State"]).unwrap(), "alert");
+        assert!(!changed.contains_key("IconName"));
+    }
+}
@@ -0,0 +1,56 @@
+//! Which desktop environment we are running under, to know where it keeps
+//! its settings.
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Desktop {
+    Kde,
+    Gnome,
+    Cinnamon,
+    Xfce,
+    Other,
+}
+
+impl Desktop {
+    /// Detect from `XDG_CURRENT_DESKTOP`, or `DESKTOP_SESSION` for older
+    /// display managers.
+    pub fn detect() -> Self {
+        let from_env = |var| std::env::var(var).ok().map(|value| Self::from_xdg(&value));
+        match from_env("XDG_CURRENT_DESKTOP") {
+            Some(Desktop::Other) | None => from_env("DESKTOP_SESSION").unwrap_or(Desktop::Other),
+            Some(desktop) => desktop,
+        }
+    }
+
+    /// Parse a colon separated `XDG_CURRENT_DESKTOP` value such as
+    /// `ubuntu:GNOME`. The first known entry wins.
+    pub fn from_xdg(value: &str) -> Self {
+        value
+            .split(':')
+            .map(|name| match name.trim().to_ascii_lowercase().as_str() {
+                "kde" | "plasma" => Desktop::Kde,
+                // Budgie, Pantheon and Unity keep their theme in the GNOME schema
+                "gnome" | "gnome-classic" | "gnome-flashback" | "ubuntu" | "budgie"
+                | "budgie-desktop" | "pantheon" | "unity" => Desktop::Gnome,
+                "x-cinnamon" | "cinnamon" => Desktop::Cinnamon,
+                "xfce" => Desktop::Xfce,
+                _ => Desktop::Other,
+            })
+            .find(|desktop| *desktop != Desktop::Other)
+            .unwrap_or(Desktop::Other)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_from_xdg() {
+        assert_eq!(Desktop::from_xdg("KDE"), Desktop::Kde);
+        assert_eq!(Desktop::from_xdg("ubuntu:GNOME"), Desktop::Gnome);
+        assert_eq!(Desktop::from_xdg("X-Cinnamon"), Desktop::Cinnamon);
+        assert_eq!(Desktop::from_xdg("XFCE"), Desktop::Xfce);
+        assert_eq!(Desktop::from_xdg("sway"), Desktop::Other);
+        assert_eq!(Desktop::from_xdg(""), Desktop::Other);
+    }
+}
@@ -0,0 +1,232 @@
+//! One tray cat per session.
+//!
+//! The first tray instance holds an advisory lock on
+//! `$XDG_RUNTIME_DIR/rustcat.lock` for as long as it runs and listens on
+//! `rustcat.sock` next to it. A later launch finds the lock taken and hands
+//! its request to the running instance over the socket instead of starting
+//! a second cat. The kernel releases the lock when a process dies, so a
+//! crash can't leave a stale lock; the socket file it leaves behind is
+//! replaced by the next instance.
+//!
+//! The socket speaks the [`super::control`] protocol, which scripts can use
+//! as well.
+
+use super::control;
+use crate::app::AppStatus;
+use crate::cli::Options;
+use crate::debug;
+use crate::events::Events;
+use std::fs::{self, File, OpenOptions, TryLockError};
+use std::io::{self, BufRead, BufReader, Write};
+use std::net::Shutdown;
+use std::os::unix::net::{UnixListener, UnixStream};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc::Sender;
+use std::sync::Arc;
+use std::thread::{self, JoinHandle};
+use std::time::Duration;
+
+/// The running tray instance
+pub struct Instance {
+    /// Held for as long as requests are served
+    lock: File,
+    listener: UnixListener,
+    socket_path: PathBuf,
+}
+
+/// Requests being answered on a background thread, until [`Server::shutdown`]
+pub struct Server {
+    stopping: Arc<AtomicBool>,
+    socket_path: PathBuf,
+    thread: JoinHandle<()>,
+}
+
+impl Instance {
+    /// Answer requests from later launches and scripts on a background
+    /// thread, passing commands on to `events`
+    pub fn serve(self, events: Sender<Events>, status: AppStatus) -> Server {
+        let stopping = Arc::new(AtomicBool::new(false));
+        let socket_path = self.socket_path.clone();
+        let thread = thread::spawn({
+            let stopping = stopping.clone();
+            move || {
+                // Move the lock into the thread too, not just the listener
+                let _lock = self.lock;
+                for stream in self.listener.incoming() {
+                    if stopping.load(Ordering::SeqCst) {
+                        break;
+                    }
+                    let stream = match stream {
+                        Ok(stream) => stream,
+                        Err(e) => {
+                            eprintln!("Failed to accept instance connection: {}", e);
+                            continue;
+                        }
+                    };
+                    let events = events.clone();
+                    let status = status.clone();
+                    thread::spawn(move || {
+                        if let Err(e) = control::handle(stream, &events, &status) {
+                            debug!("Instance connection failed: {}", e);
+                        }
+                    });
+                }
+                // Gone with the lock, so the next instance binds a fresh one
+                let _ = fs::remove_file(&self.socket_path);
+            }
+        });
+        Server {
+            stopping,
+            socket_path,
+            thread,
+        }
+    }
+}
+
+impl Server {
+    /// Stop answering requests and give up the lock, so another instance can
+    /// take over
+    pub fn shutdown(self) {
+        self.stopping.store(true, Ordering::SeqCst);
+        // Wake the listener, which is blocked waiting for a connection
+        if UnixStream::connect(&self.socket_path).is_ok() {
+            let _ = self.thread.join();
+        }
+    }
+}
+
+/// Become the tray instance, or forward `requests` to the one already
+/// running and return `None`.
+pub fn acquire(requests: &[String]) -> io::Result<Option<Instance>> {
+    let dir = dirs::runtime_dir()
+        .ok_or_else(|| io::Error::new(io::ErrorKind::NotFound, "XDG_RUNTIME_DIR is not set"))?;
+    acquire_in(&dir, requests)
+}
+
+fn acquire_in(dir: &Path, requests: &[String]) -> io::Result<Option<Instance>> {
+    let lock = OpenOptions::new()
+        .create(true)
+        .truncate(false)
+        .write(true)
+        .open(dir.join("rustcat.lock"))?;
+    let socket_path = dir.join("rustcat.sock");
+    match lock.try_lock() {
+        Ok(()) => {
+            // Left behind by an instance that crashed
+            if let Err(e) = fs::remove_file(&socket_path) {
+                if e.kind() != io::ErrorKind::NotFound {
+                    return Err(e);
+                }
+            }
+            let listener = UnixListener::bind(&socket_path)?;
+            Ok(Some(Instance {
+                lock,
+                listener,
+                socket_path,
+            }))
+        }
+        Err(TryLockError::WouldBlock) => {
+            forward(&socket_path, requests)?;
+            Ok(None)
+        }
+        Err(TryLockError::Error(e)) => Err(e),
+    }
+}
+
+/// Requests that make the running instance do what `options` ask for. A
+/// plain launch shows the About dialog, so there is some sign it was seen.
+pub fn requests(options: &Options) -> Vec<String> {
+    let mut requests = Vec::new();
+    if let Some(icon) = &options.icon {
+        requests.push(format!("icon {}", icon));
+    }
+    if let Some(theme) = options.theme {
+        requests.push(format!("theme {}", theme));
+    }
+    if let Some(metric) = &options.metric {
+        requests.push(format!("metric {}", metric));
+    }
+    if requests.is_empty() {
+        requests.push("about".to_string());
+    }
+    requests
+}
+
+fn forward(socket_path: &Path, requests: &[String]) -> io::Result<()> {
+    let mut stream = connect(socket_path)?;
+    for request in requests {
+        writeln!(stream, "{}", request)?;
+    }
+    stream.shutdown(Shutdown::Write)?;
+    for (request, reply) in requests.iter().zip(BufReader::new(stream).lines()) {
+        if let Some(reason) = reply?.strip_prefix("error: ") {
+            eprintln!("Running instance refused '{}': {}", request, reason);
+        }
+    }
+    Ok(())
+}
+
+/// Connect to the running instance, which may still be starting up: it
+/// takes the lock before it binds the socket.
+fn connect(socket_path: &Path) -> io::Result<UnixStream> {
+    let mut attempts = 0;
+    loop {
+        match UnixStream::connect(socket_path) {
+            Err(e)
+                if attempts < 20
+                    && matches!(
+                        e.kind(),
+                        io::ErrorKind::NotFound | io::ErrorKind::ConnectionRefused
+                    ) =>
+            {
+                attempts += 1;
+                thread::sleep(Duration::from_millis(100));
+            }
+            result => return result,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::PathBuf;
+    use std::sync::mpsc;
+
+    fn temp_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("rustcat-{}-{}", name, std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    #[test]
+    fn test_second_launch_forwards() {
+        let dir = temp_dir("instance");
+        let instance = acquire_in(&dir, &[]).unwrap().expect("first launch runs");
+        let (sender, receiver) = mpsc::channel();
+        let server = instance.serve(sender, AppStatus::for_tests());
+
+        let requests = ["icon parrot".to_string(), "about".to_string()];
+        assert!(acquire_in(&dir, &requests).unwrap().is_none());
+        assert_eq!(
+            receiver.recv().unwrap(),
+            Events::SetIcon("parrot".to_string())
+        );
+        assert_eq!(receiver.recv().unwrap(), Events::ShowAboutDialog);
+
+        server.shutdown();
+        // The lock is free again for the next instance
+        assert!(acquire_in(&dir, &[]).unwrap().is_some());
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_stale_socket_is_replaced() {
+        let dir = temp_dir("stale-instance");
+        // A crashed instance leaves its socket file but nothing listening
+        drop(UnixListener::bind(dir.join("rustcat.sock")).unwrap());
+        assert!(acquire_in(&dir, &[]).unwrap().is_some());
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
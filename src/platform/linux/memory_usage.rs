@@ -0,0 +1,97 @@
+use crate::platform::{MemoryInfo, MetricSource, Reading};
+use std::fs;
+use std::io;
+
+#[derive(Default)]
+pub struct LinuxMemoryMonitor {
+    info: MemoryInfo,
+}
+
+impl MetricSource for LinuxMemoryMonitor {
+    fn name(&self) -> &str {
+        "memory"
+    }
+
+    fn label(&self) -> &str {
+        "Memory"
+    }
+
+    fn sample(&mut self) -> io::Result<Reading> {
+        let contents = fs::read_to_string("/proc/meminfo")?;
+        self.info = parse_meminfo(&contents)?;
+        let value = self.info.usage_percent();
+        Ok(Reading { value, raw: value })
+    }
+
+    fn describe(&self, _reading: &Reading) -> String {
+        self.info.describe()
+    }
+}
+
+/// Parse /proc/meminfo. Every line looks like `MemAvailable:  123456 kB`.
+fn parse_meminfo(contents: &str) -> io::Result<MemoryInfo> {
+    let mut total = None;
+    let mut available = None;
+    let mut swap_total = 0;
+    let mut swap_free = 0;
+    let mut dirty = 0;
+
+    for line in contents.lines() {
+        let Some((key, rest)) = line.split_once(':') else {
+            continue;
+        };
+        let Some(kib) = rest
+            .split_whitespace()
+            .next()
+            .and_then(|v| v.parse::<u64>().ok())
+        else {
+            continue;
+        };
+        let bytes = kib * 1024;
+        match key {
+            "MemTotal" => total = Some(bytes),
+            "MemAvailable" => available = Some(bytes),
+            "SwapTotal" => swap_total = bytes,
+            "SwapFree" => swap_free = bytes,
+            "Dirty" => dirty = bytes,
+            _ => {}
+        }
+    }
+
+    // MemAvailable exists since Linux 3.14; without it there is no sane
+    // estimate of how much memory can be reclaimed.
+    let (Some(total), Some(available)) = (total, available) else {
+        return Err(io::Error::other(
+            "Unexpected /proc/meminfo format (missing MemTotal/MemAvailable)",
+        ));
+    };
+
+    Ok(MemoryInfo {
+        total,
+        available,
+        swap_total,
+        swap_used: swap_total.saturating_sub(swap_free),
+        dirty,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_meminfo() {
+        let meminfo = "MemTotal:       16000000 kB\n\
+                       MemFree:         1000000 kB\n\
+                       MemAvailable:    4000000 kB\n\
+                       SwapTotal:       2000000 kB\n\
+                       SwapFree:        1500000 kB\n\
+                       Dirty:              2048 kB\n\
+                       HugePages_Total:       0\n";
+        let info = parse_meminfo(meminfo).unwrap();
+        assert_eq!(info.total, 16_000_000 * 1024);
+        assert_eq!(info.swap_used, 500_000 * 1024);
+        assert_eq!(info.dirty, 2048 * 1024);
+        assert_eq!(info.usage_percent(), 75.0);
+    }
+}
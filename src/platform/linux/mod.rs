@@ -1,8 +1,28 @@
 pub mod app;
+pub mod appearance;
+pub mod atomic_file;
+pub mod control;
 pub mod cpu_usage;
+pub mod dbus;
+pub mod desktop;
+pub mod instance;
+pub mod memory_usage;
 pub mod settings;
 pub mod system_integration;
+#[cfg(test)]
+mod test_bus;
 
 pub use cpu_usage::LinuxCpuMonitor;
+pub use memory_usage::LinuxMemoryMonitor;
 pub use settings::LinuxSettingsManager;
-pub use system_integration::LinuxSystemIntegration;
\ No newline at end of file
+pub use system_integration::LinuxSystemIntegration;
+
+use crate::platform::MetricSource;
+
+pub fn metric_sources() -> Vec<Box<dyn MetricSource>> {
+    vec![
+        Box::new(LinuxCpuMonitor::average()),
+        Box::new(LinuxCpuMonitor::busiest_core()),
+        Box::new(LinuxMemoryMonitor::default()),
+    ]
+}
@@ -1,37 +1,40 @@
+use super::atomic_file;
+use super::desktop::Desktop;
+use crate::badge::Badge;
 use crate::icon_manager::Theme;
+use crate::migrations;
 use crate::platform::SettingsManager;
+use crate::schedule::SleepConfig;
+use crate::settings::Settings;
+use crate::smoothing::SmoothingConfig;
+use crate::speed_curve::{CurveKind, SpeedCurve};
+use crate::{debug, info, warn};
+use inotify::{Inotify, WatchMask};
 use std::fs;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 use std::process::Command;
+use std::sync::{Mutex, OnceLock};
+use std::thread;
+use std::time::Duration;
+use toml::Table;
 
 pub struct LinuxSettingsManager;
 
 impl SettingsManager for LinuxSettingsManager {
     fn get_current_icon() -> String {
-        read_setting("IconName").unwrap_or_else(|| "cat".to_string())
+        settings().icon
     }
 
     fn set_current_icon(icon_name: &str) {
-        write_setting("IconName", icon_name);
+        update_settings(|settings| settings.icon = icon_name.to_string());
     }
 
     fn get_current_theme() -> Theme {
-        if let Some(theme_str) = read_setting("Theme") {
-            match theme_str.as_str() {
-                "dark" => Theme::Dark,
-                "light" => Theme::Light,
-                _ => Theme::from_system(),
-            }
-        } else {
-            Theme::from_system()
-        }
+        settings().theme.unwrap_or_else(Theme::from_system)
     }
 
     fn set_current_theme(theme: Option<Theme>) {
-        match theme {
-            Some(theme) => write_setting("Theme", &theme.to_string()),
-            None => remove_setting("Theme"),
-        }
+        update_settings(|settings| settings.theme = theme);
     }
 
     fn is_run_on_start_enabled() -> bool {
@@ -77,53 +80,107 @@ NoDisplay=false\n"
     }
 
     fn is_dark_mode_enabled() -> bool {
-        // Prefer KDE's kreadconfig (works on Plasma 5/6)
-        for tool in ["kreadconfig6", "kreadconfig5"] {
-            let output = Command::new(tool)
-                .args(["--group", "General", "--key", "ColorScheme"])
-                .output();
-            if let Ok(out) = output {
-                if out.status.success() {
-                    let scheme = String::from_utf8_lossy(&out.stdout).trim().to_string();
-                    // KDE color scheme names containing "Dark" are dark themes
-                    // (e.g. "Breeze Dark", "Breeze-Dark")
-                    if scheme.to_lowercase().contains("dark") {
-                        return true;
-                    }
-                    if scheme.to_lowercase().contains("light") {
-                        return false;
-                    }
-                }
+        // GTK_THEME overrides the desktop's theme, e.g. `Adwaita:dark`
+        if let Ok(theme) = std::env::var("GTK_THEME") {
+            if !theme.is_empty() {
+                return theme_name_is_dark(&theme);
             }
         }
 
-        // Fallback: parse ~/.config/kdeglobals directly
-        if let Some(home) = dirs::config_dir() {
-            let kdeglobals = home.join("kdeglobals");
-            if let Ok(content) = fs::read_to_string(&kdeglobals) {
-                let mut in_general = false;
-                for line in content.lines() {
-                    let trimmed = line.trim();
-                    if trimmed.starts_with('[') {
-                        in_general = trimmed == "[General]";
-                        continue;
-                    }
-                    if in_general {
-                        if let Some((key, value)) = trimmed.split_once('=') {
-                            if key.trim() == "ColorScheme" {
-                                return value.trim().to_lowercase().contains("dark");
-                            }
-                        }
-                    }
-                }
-            }
+        let desktop = Desktop::detect();
+        let detected = match desktop {
+            Desktop::Kde => kde_dark_mode(),
+            Desktop::Gnome => gnome_dark_mode(),
+            Desktop::Cinnamon => cinnamon_dark_mode(),
+            Desktop::Xfce => xfce_dark_mode(),
+            // Unknown desktops often still run one of the two settings stores
+            Desktop::Other => kde_dark_mode().or_else(gnome_dark_mode),
+        };
+        debug!("{:?} dark mode: {:?}", desktop, detected);
+        detected.unwrap_or(false)
+    }
+
+    fn migrate_legacy_settings() {
+        // Loading upgrades older formats, see `migrations`
+        settings();
+    }
+
+    fn location() -> String {
+        settings_path().display().to_string()
+    }
+
+    fn reset_settings() -> Result<(), String> {
+        let mut cached = SETTINGS.lock().unwrap();
+        let path = settings_path();
+        create_config_dir().map_err(|e| format!("Failed to create config directory: {}", e))?;
+        let _lock = atomic_file::lock(&path)
+            .map_err(|e| format!("Failed to lock {}: {}", path.display(), e))?;
+        // Unlike other changes this one is made to a broken file too, which
+        // is kept in case it is worth fixing by hand
+        let broken = fs::read_to_string(&path).is_ok_and(|text| text.parse::<Table>().is_err());
+        if broken {
+            let backup = path.with_file_name(format!(
+                "{}.bak",
+                path.file_name().unwrap_or_default().to_string_lossy()
+            ));
+            fs::copy(&path, &backup)
+                .map_err(|e| format!("Failed to back up {}: {}", path.display(), e))?;
+            info!("The old file is kept as {}", backup.display());
         }
+        let settings = Settings::default();
+        atomic_file::write(&path, settings.to_toml().as_bytes())
+            .map_err(|e| format!("Failed to write {}: {}", path.display(), e))?;
+        *LOAD_ERROR.lock().unwrap() = None;
+        *cached = Some(settings);
+        Ok(())
+    }
 
-        false
+    fn read_value(key: &str) -> Option<String> {
+        settings()
+            .legacy_values()
+            .into_iter()
+            .find(|(k, _)| *k == key)
+            .map(|(_, value)| value)
     }
 
-    fn migrate_legacy_settings() {
-        // No legacy settings to migrate on Linux
+    fn write_value(key: &str, value: &str) {
+        update_settings(|settings| {
+            if let Err(e) = settings.apply_legacy(key, value) {
+                warn!("Ignoring setting {}={}: {}", key, value, e);
+            }
+        });
+    }
+
+    fn get_metric() -> String {
+        settings().metric
+    }
+
+    fn set_metric(name: &str) {
+        update_settings(|settings| settings.metric = name.to_string());
+    }
+
+    fn get_badge() -> Badge {
+        settings().badge
+    }
+
+    fn set_badge(badge: Badge) {
+        update_settings(|settings| settings.badge = badge);
+    }
+
+    fn get_speed_curve() -> SpeedCurve {
+        settings().speed_curve
+    }
+
+    fn set_speed_curve_kind(kind: CurveKind) {
+        update_settings(|settings| settings.speed_curve.kind = kind);
+    }
+
+    fn get_smoothing_config() -> SmoothingConfig {
+        settings().smoothing
+    }
+
+    fn get_sleep_config() -> SleepConfig {
+        settings().sleep
     }
 }
 
@@ -133,6 +190,84 @@ fn config_dir() -> PathBuf {
         .join("rustcat")
 }
 
+/// Theme names like "Breeze Dark", "Adwaita-dark" or "Mint-Y-Dark-Aqua"
+fn theme_name_is_dark(name: &str) -> bool {
+    name.to_lowercase().contains("dark")
+}
+
+/// Trimmed stdout of a successful command
+fn command_output(program: &str, args: &[&str]) -> Option<String> {
+    let output = Command::new(program).args(args).output().ok()?;
+    if !output.status.success() {
+        return None;
+    }
+    Some(String::from_utf8_lossy(&output.stdout).trim().to_string())
+}
+
+/// A gsettings string key, without the quotes `gsettings get` prints
+fn gsettings_get(schema: &str, key: &str) -> Option<String> {
+    let value = command_output("gsettings", &["get", schema, key])?;
+    Some(value.trim_matches('\'').to_string())
+}
+
+fn kde_dark_mode() -> Option<bool> {
+    // Prefer KDE's kreadconfig (works on Plasma 5/6)
+    for tool in ["kreadconfig6", "kreadconfig5"] {
+        if let Some(scheme) = command_output(tool, &["--group", "General", "--key", "ColorScheme"])
+        {
+            // KDE color scheme names containing "Dark" are dark themes
+            // (e.g. "Breeze Dark", "Breeze-Dark")
+            if theme_name_is_dark(&scheme) {
+                return Some(true);
+            }
+            if scheme.to_lowercase().contains("light") {
+                return Some(false);
+            }
+        }
+    }
+
+    // Fallback: parse ~/.config/kdeglobals directly
+    let content = fs::read_to_string(dirs::config_dir()?.join("kdeglobals")).ok()?;
+    let mut in_general = false;
+    for line in content.lines() {
+        let trimmed = line.trim();
+        if trimmed.starts_with('[') {
+            in_general = trimmed == "[General]";
+            continue;
+        }
+        if in_general {
+            if let Some((key, value)) = trimmed.split_once('=') {
+                if key.trim() == "ColorScheme" {
+                    return Some(theme_name_is_dark(value));
+                }
+            }
+        }
+    }
+    None
+}
+
+fn gnome_dark_mode() -> Option<bool> {
+    // GNOME 42+ has an explicit preference; "default" means the theme decides
+    match gsettings_get("org.gnome.desktop.interface", "color-scheme").as_deref() {
+        Some("prefer-dark") => return Some(true),
+        Some("prefer-light") => return Some(false),
+        _ => {}
+    }
+    gsettings_get("org.gnome.desktop.interface", "gtk-theme")
+        .map(|theme| theme_name_is_dark(&theme))
+}
+
+fn cinnamon_dark_mode() -> Option<bool> {
+    gsettings_get("org.cinnamon.desktop.interface", "gtk-theme")
+        .map(|theme| theme_name_is_dark(&theme))
+        .or_else(gnome_dark_mode)
+}
+
+fn xfce_dark_mode() -> Option<bool> {
+    command_output("xfconf-query", &["-c", "xsettings", "-p", "/Net/ThemeName"])
+        .map(|theme| theme_name_is_dark(&theme))
+}
+
 /// Escape a string for use as the value of a freedesktop desktop entry `Exec`
 /// key. Reserved characters must be backslash-escaped, otherwise a path with
 /// spaces (e.g. `/home/me/Rust Cat/rust_cat`) is split into tokens at login.
@@ -151,78 +286,364 @@ fn escape_exec_value(s: &str) -> String {
     out
 }
 
+/// Settings file given with `--config`
+static SETTINGS_PATH: OnceLock<PathBuf> = OnceLock::new();
+
+/// Use `path` instead of the settings file in the config directory. Must be
+/// called before the settings are first read.
+pub fn set_settings_path(path: &Path) -> std::io::Result<()> {
+    // The watcher needs a directory to watch, even for a bare file name
+    let _ = SETTINGS_PATH.set(std::path::absolute(path)?);
+    Ok(())
+}
+
 fn settings_path() -> PathBuf {
-    config_dir().join("settings.conf")
+    SETTINGS_PATH
+        .get()
+        .cloned()
+        .unwrap_or_else(|| config_dir().join("settings.toml"))
 }
 
-fn autostart_desktop_path() -> PathBuf {
-    dirs::config_dir()
-        .unwrap_or_else(|| PathBuf::from("/tmp"))
-        .join("autostart")
-        .join("rustcat.desktop")
+/// `key=value` lines used before settings.toml. Not converted into a file
+/// given with `--config`.
+fn legacy_settings_path() -> Option<PathBuf> {
+    SETTINGS_PATH
+        .get()
+        .is_none()
+        .then(|| config_dir().join("settings.conf"))
 }
 
-fn read_setting(key: &str) -> Option<String> {
-    let content = fs::read_to_string(settings_path()).ok()?;
-    for line in content.lines() {
-        if let Some((k, v)) = line.split_once('=') {
-            if k.trim() == key {
-                return Some(v.trim().to_string());
+/// Settings as last read from or written to disk
+static SETTINGS: Mutex<Option<Settings>> = Mutex::new(None);
+/// Why the settings file last failed to load, so it is reported only once
+static LOAD_ERROR: Mutex<Option<String>> = Mutex::new(None);
+
+fn settings() -> Settings {
+    SETTINGS
+        .lock()
+        .unwrap()
+        .get_or_insert_with(|| valid_settings().unwrap_or_default())
+        .clone()
+}
+
+/// The settings file, or `None` if it can't be read or parsed, which is
+/// reported once per error. Until it is fixed the file is neither applied
+/// nor overwritten, so a typo or a half-written save can't reset the
+/// user's settings.
+fn valid_settings() -> Option<Settings> {
+    let result = load_settings();
+    let mut last_error = LOAD_ERROR.lock().unwrap();
+    match result {
+        Ok(settings) => {
+            *last_error = None;
+            Some(settings)
+        }
+        Err(e) => {
+            if last_error.as_ref() != Some(&e) {
+                warn!("{}; keeping the current settings until it is fixed", e);
+                *last_error = Some(e);
             }
+            None
         }
     }
-    None
 }
 
-fn write_setting(key: &str, value: &str) {
+/// Change the settings and write them back. The file is re-read under the
+/// write lock so changes made meanwhile by another instance or an editor are
+/// kept. While the file is broken the change is only made in memory.
+fn update_settings(change: impl FnOnce(&mut Settings)) {
+    let mut cached = SETTINGS.lock().unwrap();
     let path = settings_path();
-    if let Some(parent) = path.parent() {
-        if let Err(e) = fs::create_dir_all(parent) {
-            eprintln!("Failed to create config directory: {}", e);
+    if let Err(e) = create_config_dir() {
+        eprintln!("Failed to create config directory: {}", e);
+        return;
+    }
+    let _lock = match atomic_file::lock(&path) {
+        Ok(lock) => lock,
+        Err(e) => {
+            eprintln!("Failed to lock {}: {}", path.display(), e);
             return;
         }
+    };
+    let Some(mut settings) = valid_settings() else {
+        warn!("Not saving to {} until it is fixed", path.display());
+        change(cached.get_or_insert_with(Settings::default));
+        return;
+    };
+    change(&mut settings);
+    save_settings(&settings);
+    *cached = Some(settings);
+}
+
+/// Re-read the settings file after a change on disk. Returns `false` if it
+/// still holds what was last read or written, e.g. after our own writes, or
+/// if it doesn't parse.
+fn reload_settings() -> bool {
+    let mut cached = SETTINGS.lock().unwrap();
+    let Some(settings) = valid_settings() else {
+        return false;
+    };
+    if cached.as_ref() == Some(&settings) {
+        return false;
     }
+    debug!("{} changed on disk", settings_path().display());
+    *cached = Some(settings);
+    true
+}
 
-    let mut content = fs::read_to_string(&path).unwrap_or_default();
-    let mut found = false;
-    let mut new_lines = Vec::new();
-    for line in content.lines() {
-        if let Some((k, _)) = line.split_once('=') {
-            if k.trim() == key {
-                new_lines.push(format!("{}={}", key, value));
-                found = true;
-                continue;
+/// Watch the settings file and call `on_change` whenever it is edited, by
+/// hand or by a dotfile tool, until `on_change` returns `false`.
+pub fn watch_settings(mut on_change: impl FnMut() -> bool + Send + 'static) {
+    if let Err(e) = create_config_dir() {
+        eprintln!("Failed to create config directory: {}", e);
+        return;
+    }
+    thread::spawn(move || {
+        let result = watch_file(&settings_path(), || !reload_settings() || on_change());
+        if let Err(e) = result {
+            eprintln!("Failed to watch {}: {}", settings_path().display(), e);
+        }
+    });
+}
+
+/// How long a change to the settings file has to settle before it is read
+const SETTLE_TIME: Duration = Duration::from_millis(100);
+
+/// Call `on_change` after every write to `path` until it returns `false`.
+/// The directory is watched rather than the file, since atomic saves (ours
+/// and most editors') replace the file instead of writing to it.
+fn watch_file(path: &Path, mut on_change: impl FnMut() -> bool) -> std::io::Result<()> {
+    let (Some(dir), Some(name)) = (path.parent(), path.file_name()) else {
+        return Ok(());
+    };
+    let mut inotify = Inotify::init()?;
+    inotify
+        .watches()
+        .add(dir, WatchMask::CLOSE_WRITE | WatchMask::MOVED_TO)?;
+    let mut buffer = [0; 4096];
+    loop {
+        let events = inotify.read_events_blocking(&mut buffer)?;
+        if !events.into_iter().any(|event| event.name == Some(name)) {
+            continue;
+        }
+        // Tools that write in several steps close the file more than once;
+        // read it after they're done, once for all of their events
+        thread::sleep(SETTLE_TIME);
+        while inotify.read_events(&mut buffer).is_ok() {}
+        if !on_change() {
+            return Ok(());
+        }
+    }
+}
+
+/// Read the settings file, upgrading older formats and writing the defaults
+/// on first run. Fails if the file exists but can't be read or parsed.
+fn load_settings() -> Result<Settings, String> {
+    let path = settings_path();
+    let (mut table, version, source) = match fs::read_to_string(&path) {
+        Ok(text) => match text.parse::<Table>() {
+            Ok(table) => {
+                let version = migrations::version_of(&table);
+                (table, version, path.clone())
+            }
+            Err(e) => {
+                let line = e
+                    .span()
+                    .map_or(0, |span| text[..span.start].matches('\n').count() + 1);
+                return Err(format!("{}:{}: {}", path.display(), line, e.message()));
+            }
+        },
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => {
+            let legacy = legacy_settings_path().and_then(|legacy_path| {
+                Some((fs::read_to_string(&legacy_path).ok()?, legacy_path))
+            });
+            match legacy {
+                Some((text, legacy_path)) => (migrations::parse_conf(&text), 0, legacy_path),
+                None => {
+                    // First run: write the defaults so there is a file to edit
+                    let settings = Settings::default();
+                    save_settings(&settings);
+                    return Ok(settings);
+                }
             }
         }
-        new_lines.push(line.to_string());
+        Err(e) => return Err(format!("Failed to read {}: {}", path.display(), e)),
+    };
+
+    if version > migrations::CURRENT_VERSION {
+        warn!(
+            "{} is from a newer RustCat (format {}), some settings may be ignored",
+            path.display(),
+            version
+        );
     }
-    if !found {
-        new_lines.push(format!("{}={}", key, value));
+    let upgrade = version < migrations::CURRENT_VERSION;
+    let mut backed_up = false;
+    if upgrade {
+        // Keep the original around in case the upgrade loses something
+        let backup = source.with_file_name(format!(
+            "{}.v{}.bak",
+            source.file_name().unwrap_or_default().to_string_lossy(),
+            version
+        ));
+        match fs::copy(&source, &backup) {
+            Ok(_) => {
+                backed_up = true;
+                info!(
+                    "Upgrading {} from format {} to {}, the old file is kept as {}",
+                    source.display(),
+                    version,
+                    migrations::CURRENT_VERSION,
+                    backup.display()
+                );
+            }
+            // Upgraded all the same, the original is just left where it is
+            Err(e) => eprintln!("Failed to back up {}: {}", source.display(), e),
+        }
+        for change in migrations::migrate(&mut table, version) {
+            info!("  {}", change);
+        }
     }
 
-    content = new_lines.join("\n");
-    if !content.ends_with('\n') {
-        content.push('\n');
+    let (settings, warnings) = Settings::from_table(&table);
+    for warning in warnings {
+        warn!("{}: {}", path.display(), warning);
     }
+    // Without a backup, only a file converted from settings.conf is written,
+    // which leaves the original alone
+    if upgrade && (backed_up || source != path) {
+        save_settings(&settings);
+        if backed_up && source != path {
+            // Converted from settings.conf, which the backup now holds
+            if let Err(e) = fs::remove_file(&source) {
+                eprintln!("Failed to remove {}: {}", source.display(), e);
+            }
+        }
+    }
+    Ok(settings)
+}
 
-    if let Err(e) = fs::write(&path, content) {
-        eprintln!("Failed to write setting '{}': {}", key, e);
+/// Create the directory holding the settings file
+fn create_config_dir() -> std::io::Result<()> {
+    match settings_path().parent() {
+        Some(dir) => fs::create_dir_all(dir),
+        None => Ok(()),
     }
 }
 
-fn remove_setting(key: &str) {
+fn save_settings(settings: &Settings) {
     let path = settings_path();
-    let Ok(content) = fs::read_to_string(&path) else {
+    if let Err(e) = create_config_dir() {
+        eprintln!("Failed to create config directory: {}", e);
         return;
-    };
-    let new_lines: Vec<String> = content
-        .lines()
-        .filter(|line| {
-            line.split_once('=').map(|(k, _)| k.trim() != key).unwrap_or(true)
-        })
-        .map(String::from)
-        .collect();
-    if let Err(e) = fs::write(&path, format!("{}\n", new_lines.join("\n"))) {
-        eprintln!("Failed to remove setting '{}': {}", key, e);
-    }
-}
\ No newline at end of file
+    }
+    if let Err(e) = atomic_file::write(&path, settings.to_toml().as_bytes()) {
+        eprintln!("Failed to write {}: {}", path.display(), e);
+    }
+}
+
+fn autostart_desktop_path() -> PathBuf {
+    dirs::config_dir()
+        .unwrap_or_else(|| PathBuf::from("/tmp"))
+        .join("autostart")
+        .join("rustcat.desktop")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::mpsc;
+
+    #[test]
+    fn test_watch_file_sees_replacements() {
+        let dir = std::env::temp_dir().join(format!("rustcat-watch-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("settings.toml");
+
+        let (sender, receiver) = mpsc::channel();
+        let watched = path.clone();
+        thread::spawn(move || watch_file(&watched, || sender.send(()).is_ok()));
+        // Give the watcher time to start
+        thread::sleep(Duration::from_millis(200));
+
+        // Other files in the directory are ignored
+        fs::write(dir.join("other.toml"), "x").unwrap();
+        assert!(receiver.recv_timeout(Duration::from_millis(200)).is_err());
+
+        // Atomic replacement and an in-place edit both count
+        atomic_file::write(&path, b"icon = \"parrot\"").unwrap();
+        receiver.recv_timeout(Duration::from_secs(5)).unwrap();
+        fs::write(&path, "icon = \"cat\"").unwrap();
+        receiver.recv_timeout(Duration::from_secs(5)).unwrap();
+
+        // A write in two steps is read once, after the second
+        fs::write(&path, "icon = ").unwrap();
+        fs::write(&path, "icon = \"parrot\"").unwrap();
+        receiver.recv_timeout(Duration::from_secs(5)).unwrap();
+        assert!(receiver.recv_timeout(SETTLE_TIME * 3).is_err());
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_broken_file_is_left_alone_until_reset() {
+        // The only test using the settings file
+        let dir = std::env::temp_dir().join(format!("rustcat-broken-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("settings.toml");
+        set_settings_path(&path).unwrap();
+        assert_eq!(settings_path(), path);
+
+        // Defaults until it is fixed, changed in memory only
+        let broken = "icon = \"parrot\"\ntheme = \n";
+        fs::write(&path, broken).unwrap();
+        assert_eq!(LinuxSettingsManager::get_current_icon(), "cat");
+        LinuxSettingsManager::set_current_icon("parrot");
+        assert_eq!(LinuxSettingsManager::get_current_icon(), "parrot");
+        assert_eq!(fs::read_to_string(&path).unwrap(), broken);
+        assert!(!reload_settings());
+
+        // Once fixed it is read again, and keys this version doesn't know
+        // survive saving
+        let fixed = format!(
+            "version = {}\nicon = \"parrot\"\nfuture = {{ size = 2 }}\n[sleep]\nnap = true\n",
+            migrations::CURRENT_VERSION
+        );
+        fs::write(&path, fixed).unwrap();
+        assert!(reload_settings());
+        LinuxSettingsManager::set_current_theme(Some(Theme::Light));
+        let text = fs::read_to_string(&path).unwrap();
+        let (settings, warnings) = Settings::from_table(&text.parse().unwrap());
+        assert_eq!(settings.icon, "parrot");
+        assert_eq!(settings.theme, Some(Theme::Light));
+        assert_eq!(settings.unknown["sleep"]["nap"], toml::Value::Boolean(true));
+        assert_eq!(warnings.len(), 2, "{:?}", warnings);
+        assert!(text.contains("\nfuture = { size = 2 }\n"), "{}", text);
+
+        // Resetting is the way out of a broken file, so it does write
+        fs::write(&path, broken).unwrap();
+        assert!(!reload_settings());
+        LinuxSettingsManager::reset_settings().unwrap();
+        assert_eq!(
+            fs::read_to_string(dir.join("settings.toml.bak")).unwrap(),
+            broken
+        );
+        let text = fs::read_to_string(&path).unwrap();
+        assert_eq!(
+            Settings::from_table(&text.parse().unwrap()).0,
+            Settings::default()
+        );
+        assert_eq!(LinuxSettingsManager::get_current_icon(), "cat");
+
+        // An upgrade that can't be backed up still applies, in memory only
+        let old = fs::read_to_string("tests/fixtures/settings/v1-unversioned.toml").unwrap();
+        fs::write(&path, &old).unwrap();
+        fs::create_dir(dir.join("settings.toml.v1.bak")).unwrap();
+        let settings = load_settings().unwrap();
+        assert_eq!(settings.icon, "parrot");
+        assert_eq!(settings.metric, "memory");
+        assert_eq!(fs::read_to_string(&path).unwrap(), old);
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -1,4 +1,5 @@
 use crate::platform::SystemIntegration;
+use crate::schedule::LocalTime;
 use std::process::Command;
 
 pub struct LinuxSystemIntegration;
@@ -53,20 +54,27 @@ impl SystemIntegration for LinuxSystemIntegration {
         Err("No system monitor found (tried plasma-systemmonitor, ksysguard, gnome-system-monitor, htop)".into())
     }
 
-    fn get_local_hour() -> u32 {
+    fn get_local_time() -> LocalTime {
+        // %u is the ISO weekday, 1 = Monday .. 7 = Sunday
         let output = Command::new("date")
-            .arg("+%H")
+            .arg("+%u %H %M")
             .output()
             .unwrap_or_else(|_| std::process::Output {
                 status: std::process::ExitStatus::default(),
-                stdout: b"0".to_vec(),
+                stdout: b"1 0 0".to_vec(),
                 stderr: Vec::new(),
             });
 
-        String::from_utf8_lossy(&output.stdout)
-            .trim()
-            .parse()
-            .unwrap_or(0)
+        let output = String::from_utf8_lossy(&output.stdout);
+        let mut fields = output
+            .split_whitespace()
+            .map(|field| field.parse::<u32>().unwrap_or(0));
+        let mut next = || fields.next().unwrap_or(0);
+        LocalTime {
+            weekday: next().saturating_sub(1) % 7,
+            hour: next(),
+            minute: next(),
+        }
     }
 }
 
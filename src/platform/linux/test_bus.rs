@@ -0,0 +1,43 @@
+//! A private D-Bus session bus for tests, so they never touch the user's
+//! session.
+
+use std::io::{BufRead, BufReader};
+use std::process::{Child, Command, Stdio};
+
+/// A `dbus-daemon` of its own, killed when dropped so a failing test doesn't
+/// leave it running
+pub struct TestBus {
+    daemon: Child,
+    address: String,
+}
+
+impl TestBus {
+    /// Start the daemon, or `None` when `dbus-daemon` isn't installed
+    pub fn start() -> Option<Self> {
+        let mut daemon = Command::new("dbus-daemon")
+            .args(["--session", "--nofork", "--print-address"])
+            .stdout(Stdio::piped())
+            .spawn()
+            .ok()?;
+        let stdout = daemon.stdout.take().unwrap();
+        // Guard the daemon before anything can fail
+        let mut bus = TestBus {
+            daemon,
+            address: String::new(),
+        };
+        BufReader::new(stdout).read_line(&mut bus.address).unwrap();
+        bus.address = bus.address.trim().to_string();
+        Some(bus)
+    }
+
+    pub fn address(&self) -> &str {
+        &self.address
+    }
+}
+
+impl Drop for TestBus {
+    fn drop(&mut self) {
+        let _ = self.daemon.kill();
+        let _ = self.daemon.wait();
+    }
+}
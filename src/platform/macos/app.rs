@@ -1,5 +1,5 @@
-use crate::app::App;
-use crate::icon_manager::{IconManager, Theme};
+use crate::app::{App, Overrides};
+use crate::icon_manager::IconManager;
 use objc2::rc::Retained;
 use objc2_app_kit::{NSApplication, NSApplicationActivationPolicy};
 use objc2_foundation::MainThreadMarker;
@@ -14,14 +14,15 @@ impl MacosApp {
     pub fn new(
         icon_manager: IconManager,
         initial_icon: &str,
-        initial_theme: Option<Theme>,
+        overrides: Overrides,
+        show_tray: bool,
     ) -> Result<Self, Box<dyn std::error::Error>> {
         // Initialize NSApplication for macOS
         let mtm = unsafe { MainThreadMarker::new_unchecked() };
         let ns_app = NSApplication::sharedApplication(mtm);
         ns_app.setActivationPolicy(NSApplicationActivationPolicy::Accessory);
 
-        let app = Arc::new(App::new(icon_manager, initial_icon, initial_theme)?);
+        let app = Arc::new(App::new(icon_manager, initial_icon, overrides, show_tray)?);
         Ok(MacosApp { app, ns_app })
     }
 
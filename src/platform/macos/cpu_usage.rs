@@ -1,10 +1,10 @@
-use crate::platform::CpuMonitor;
+use crate::platform::{MetricSource, Reading};
 use std::io;
-use std::sync::Mutex;
 
-pub struct MacosCpuMonitor;
-
-static CPU_STATE: Mutex<Option<(f64, f64)>> = Mutex::new(None);
+#[derive(Default)]
+pub struct MacosCpuMonitor {
+    prev: Option<(f64, f64)>,
+}
 
 // macOS system types
 #[repr(C)]
@@ -31,8 +31,16 @@ extern "C" {
     fn mach_host_self() -> u32;
 }
 
-impl CpuMonitor for MacosCpuMonitor {
-    fn get_cpu_usage() -> io::Result<f64> {
+impl MetricSource for MacosCpuMonitor {
+    fn name(&self) -> &str {
+        "cpu"
+    }
+
+    fn label(&self) -> &str {
+        "CPU"
+    }
+
+    fn sample(&mut self) -> io::Result<Reading> {
         let mut cpu_info = HostCpuLoadInfo { cpu_ticks: [0; 4] };
         let mut count = HOST_CPU_LOAD_INFO_COUNT;
 
@@ -59,8 +67,7 @@ impl CpuMonitor for MacosCpuMonitor {
 
         let total_ticks = user_ticks + system_ticks + idle_ticks + nice_ticks;
 
-        let mut state = CPU_STATE.lock().unwrap();
-        let usage = if let Some((prev_total, prev_idle)) = *state {
+        let usage = if let Some((prev_total, prev_idle)) = self.prev {
             let total_diff = total_ticks - prev_total;
             let idle_diff = idle_ticks - prev_idle;
             if total_diff > 0.0 {
@@ -72,7 +79,10 @@ impl CpuMonitor for MacosCpuMonitor {
             0.0 // First call, return 0 usage
         };
 
-        *state = Some((total_ticks, idle_ticks));
-        Ok(usage)
+        self.prev = Some((total_ticks, idle_ticks));
+        Ok(Reading {
+            value: usage,
+            raw: usage,
+        })
     }
 }
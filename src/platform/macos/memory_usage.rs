@@ -0,0 +1,153 @@
+use crate::platform::{MemoryInfo, MetricSource, Reading};
+use std::io;
+use std::os::raw::{c_char, c_void};
+
+#[derive(Default)]
+pub struct MacosMemoryMonitor {
+    info: MemoryInfo,
+}
+
+// macOS system types (only a few fields are read, the rest fix the layout)
+#[allow(dead_code)]
+#[repr(C)]
+#[derive(Default)]
+struct VmStatistics64 {
+    free_count: u32,
+    active_count: u32,
+    inactive_count: u32,
+    wire_count: u32,
+    zero_fill_count: u64,
+    reactivations: u64,
+    pageins: u64,
+    pageouts: u64,
+    faults: u64,
+    cow_faults: u64,
+    lookups: u64,
+    hits: u64,
+    purges: u64,
+    purgeable_count: u32,
+    speculative_count: u32,
+    decompressions: u64,
+    compressions: u64,
+    swapins: u64,
+    swapouts: u64,
+    compressor_page_count: u32,
+    throttled_count: u32,
+    external_page_count: u32,
+    internal_page_count: u32,
+    total_uncompressed_pages_in_compressor: u64,
+}
+
+#[allow(dead_code)]
+#[repr(C)]
+#[derive(Default)]
+struct XswUsage {
+    xsu_total: u64,
+    xsu_avail: u64,
+    xsu_used: u64,
+    xsu_pagesize: u32,
+    xsu_encrypted: i32,
+}
+
+const HOST_VM_INFO64: i32 = 4;
+const HOST_VM_INFO64_COUNT: u32 =
+    (std::mem::size_of::<VmStatistics64>() / std::mem::size_of::<i32>()) as u32;
+
+extern "C" {
+    fn host_statistics64(
+        host_priv: u32,
+        flavor: i32,
+        host_info_out: *mut VmStatistics64,
+        host_info_outCnt: *mut u32,
+    ) -> i32;
+
+    fn host_page_size(host: u32, page_size: *mut usize) -> i32;
+
+    fn mach_host_self() -> u32;
+
+    fn sysctlbyname(
+        name: *const c_char,
+        oldp: *mut c_void,
+        oldlenp: *mut usize,
+        newp: *mut c_void,
+        newlen: usize,
+    ) -> i32;
+}
+
+impl MetricSource for MacosMemoryMonitor {
+    fn name(&self) -> &str {
+        "memory"
+    }
+
+    fn label(&self) -> &str {
+        "Memory"
+    }
+
+    fn sample(&mut self) -> io::Result<Reading> {
+        let mut vm_info = VmStatistics64::default();
+        let mut count = HOST_VM_INFO64_COUNT;
+        let mut page_size: usize = 0;
+
+        let result = unsafe {
+            let host = mach_host_self();
+            let result = host_page_size(host, &mut page_size);
+            if result != 0 {
+                result
+            } else {
+                host_statistics64(host, HOST_VM_INFO64, &mut vm_info, &mut count)
+            }
+        };
+        if result != 0 {
+            return Err(io::Error::other(format!(
+                "Failed to get VM statistics: {}",
+                result
+            )));
+        }
+
+        let mut total: u64 = 0;
+        sysctl(c"hw.memsize", &mut total)?;
+        let mut swap = XswUsage::default();
+        sysctl(c"vm.swapusage", &mut swap)?;
+
+        // Same notion of "used" as Activity Monitor: app memory (anonymous
+        // pages minus purgeable ones) + wired + compressed.
+        let page_size = page_size as u64;
+        let used_pages = (vm_info.internal_page_count as u64)
+            .saturating_sub(vm_info.purgeable_count as u64)
+            + vm_info.wire_count as u64
+            + vm_info.compressor_page_count as u64;
+
+        self.info = MemoryInfo {
+            total,
+            available: total.saturating_sub(used_pages * page_size),
+            swap_total: swap.xsu_total,
+            swap_used: swap.xsu_used,
+            // macOS does not expose a dirty page counter
+            dirty: 0,
+        };
+        let value = self.info.usage_percent();
+        Ok(Reading { value, raw: value })
+    }
+
+    fn describe(&self, _reading: &Reading) -> String {
+        self.info.describe()
+    }
+}
+
+/// Read a fixed-size sysctl value into `out`.
+fn sysctl<T>(name: &std::ffi::CStr, out: &mut T) -> io::Result<()> {
+    let mut len = std::mem::size_of::<T>();
+    let result = unsafe {
+        sysctlbyname(
+            name.as_ptr(),
+            out as *mut T as *mut c_void,
+            &mut len,
+            std::ptr::null_mut(),
+            0,
+        )
+    };
+    if result != 0 {
+        return Err(io::Error::last_os_error());
+    }
+    Ok(())
+}
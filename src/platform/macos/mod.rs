@@ -1,8 +1,19 @@
 pub mod app;
 pub mod cpu_usage;
+pub mod memory_usage;
 pub mod settings;
 pub mod system_integration;
 
 pub use cpu_usage::MacosCpuMonitor;
+pub use memory_usage::MacosMemoryMonitor;
 pub use settings::MacosSettingsManager;
 pub use system_integration::MacosSystemIntegration;
+
+use crate::platform::MetricSource;
+
+pub fn metric_sources() -> Vec<Box<dyn MetricSource>> {
+    vec![
+        Box::new(MacosCpuMonitor::default()),
+        Box::new(MacosMemoryMonitor::default()),
+    ]
+}
@@ -110,6 +110,27 @@ impl SettingsManager for MacosSettingsManager {
     fn migrate_legacy_settings() {
         // No legacy settings to migrate on macOS
     }
+
+    fn location() -> String {
+        "defaults domain com.bearice.rustcat".to_string()
+    }
+
+    fn reset_settings() -> Result<(), String> {
+        // Fails when nothing is stored, which is as good as reset
+        Command::new("defaults")
+            .args(&["delete", "com.bearice.rustcat"])
+            .status()
+            .map(|_| ())
+            .map_err(|e| format!("Failed to run defaults: {}", e))
+    }
+
+    fn read_value(key: &str) -> Option<String> {
+        get_preference(key)
+    }
+
+    fn write_value(key: &str, value: &str) {
+        set_preference(key, value);
+    }
 }
 
 fn get_preference(key: &str) -> Option<String> {
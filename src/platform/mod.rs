@@ -1,3 +1,7 @@
+use crate::badge::Badge;
+use crate::schedule::{LocalTime, SleepConfig, SleepSchedule};
+use crate::smoothing::SmoothingConfig;
+use crate::speed_curve::{CurveKind, SpeedCurve};
 use std::io;
 
 #[cfg(target_os = "linux")]
@@ -7,10 +11,80 @@ pub mod macos;
 #[cfg(windows)]
 pub mod windows;
 
-/// Cross-platform CPU usage monitoring trait
-pub trait CpuMonitor {
-    /// Returns CPU usage percentage as a float (0.0 to 100.0)
-    fn get_cpu_usage() -> io::Result<f64>;
+/// One sample from a [`MetricSource`]
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Reading {
+    /// Value normalized to 0.0–100.0; this is what drives the animation
+    pub value: f64,
+    /// Value in the source's own unit, for display
+    pub raw: f64,
+}
+
+/// A system metric the animation can follow.
+///
+/// Unlike the other platform traits this one is object-safe and instances
+/// carry their own sampling state, so several sources can live side by side
+/// in a [`crate::metrics::MetricRegistry`] and be switched at runtime.
+pub trait MetricSource: Send {
+    /// Stable identifier stored in settings, e.g. `cpu`
+    fn name(&self) -> &str;
+    /// Display label for menus and the tooltip, e.g. `CPU`
+    fn label(&self) -> &str;
+    /// Unit of [`Reading::raw`]
+    fn unit(&self) -> &str {
+        "%"
+    }
+    /// Takes a new sample. Sources based on counters report 0 on the first call.
+    fn sample(&mut self) -> io::Result<Reading>;
+    /// Tooltip text for a reading returned by the last `sample` call
+    fn describe(&self, reading: &Reading) -> String {
+        format!("{} Usage: {:.2}{}", self.label(), reading.raw, self.unit())
+    }
+}
+
+/// Snapshot of system memory counters, all in bytes. Counters a platform
+/// does not expose are reported as 0.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct MemoryInfo {
+    pub total: u64,
+    pub available: u64,
+    pub swap_total: u64,
+    pub swap_used: u64,
+    pub dirty: u64,
+}
+
+impl MemoryInfo {
+    /// Memory in use (not reclaimable without swapping) as a percentage (0.0 to 100.0)
+    pub fn usage_percent(&self) -> f64 {
+        if self.total == 0 {
+            return 0.0;
+        }
+        let used = self.total.saturating_sub(self.available);
+        (used as f64 / self.total as f64 * 100.0).clamp(0.0, 100.0)
+    }
+
+    /// Tooltip text shared by the platform memory sources
+    pub fn describe(&self) -> String {
+        format!(
+            "Memory Usage: {:.2}% (swap: {} of {}, dirty: {})",
+            self.usage_percent(),
+            format_bytes(self.swap_used),
+            format_bytes(self.swap_total),
+            format_bytes(self.dirty)
+        )
+    }
+}
+
+/// Format a byte count for the tooltip, e.g. `1.5 GiB`.
+fn format_bytes(bytes: u64) -> String {
+    const MIB: f64 = 1024.0 * 1024.0;
+    const GIB: f64 = MIB * 1024.0;
+    let bytes = bytes as f64;
+    if bytes >= GIB {
+        format!("{:.1} GiB", bytes / GIB)
+    } else {
+        format!("{:.0} MiB", bytes / MIB)
+    }
 }
 
 /// Cross-platform settings management trait
@@ -23,23 +97,111 @@ pub trait SettingsManager {
     fn set_run_on_start(enable: bool);
     fn is_dark_mode_enabled() -> bool;
     fn migrate_legacy_settings();
+    /// Where the settings are stored, for display
+    fn location() -> String;
+    /// Forget every stored setting so the defaults apply again. Run on
+    /// start is left alone. Fails with the reason if nothing was reset.
+    fn reset_settings() -> Result<(), String>;
+
+    /// Reads a raw setting value from the platform's settings store.
+    fn read_value(key: &str) -> Option<String>;
+    /// Stores a raw setting value in the platform's settings store.
+    fn write_value(key: &str, value: &str);
+
+    /// Name of the [`MetricSource`] that drives the animation.
+    fn get_metric() -> String {
+        Self::read_value("Metric").unwrap_or_else(|| "cpu".to_string())
+    }
+    fn set_metric(name: &str) {
+        Self::write_value("Metric", name);
+    }
+
+    /// Corner the usage percentage is drawn in, from `UsageBadge`
+    fn get_badge() -> Badge {
+        Self::read_value("UsageBadge")
+            .and_then(|name| Badge::from_name(&name))
+            .unwrap_or_default()
+    }
+    fn set_badge(badge: Badge) {
+        Self::write_value("UsageBadge", &badge.to_string());
+    }
+
+    /// Speed curve from `SpeedCurve`, `MinFrameDelay`, `MaxFrameDelay` and
+    /// `SpeedCurvePoints`; missing or invalid values fall back to defaults.
+    fn get_speed_curve() -> SpeedCurve {
+        let default = SpeedCurve::default();
+        let read_delay = |key: &str, default: u64| {
+            Self::read_value(key)
+                .and_then(|v| v.parse::<u64>().ok())
+                .unwrap_or(default)
+        };
+        SpeedCurve {
+            kind: Self::read_value("SpeedCurve")
+                .and_then(|name| CurveKind::from_name(&name))
+                .unwrap_or(default.kind),
+            min_delay: read_delay("MinFrameDelay", default.min_delay),
+            max_delay: read_delay("MaxFrameDelay", default.max_delay),
+            points: Self::read_value("SpeedCurvePoints")
+                .and_then(|points| SpeedCurve::parse_points(&points))
+                .unwrap_or_default(),
+        }
+    }
+    fn set_speed_curve_kind(kind: CurveKind) {
+        Self::write_value("SpeedCurve", &kind.to_string());
+    }
+
+    /// Smoothing from `SmoothingTimeConstant` (seconds), `MedianWindow`
+    /// (samples) and `Hysteresis` (percentage points).
+    fn get_smoothing_config() -> SmoothingConfig {
+        let default = SmoothingConfig::default();
+        SmoothingConfig {
+            time_constant: Self::read_value("SmoothingTimeConstant")
+                .and_then(|v| v.parse::<f64>().ok())
+                .filter(|v| *v >= 0.0)
+                .unwrap_or(default.time_constant),
+            median_window: Self::read_value("MedianWindow")
+                .and_then(|v| v.parse::<usize>().ok())
+                .filter(|v| *v >= 1)
+                .unwrap_or(default.median_window),
+            hysteresis: Self::read_value("Hysteresis")
+                .and_then(|v| v.parse::<f64>().ok())
+                .filter(|v| *v >= 0.0)
+                .unwrap_or(default.hysteresis),
+        }
+    }
+
+    /// Sleep behaviour from `SleepSchedule`, `IdleSeconds` and `IdlePercent`.
+    fn get_sleep_config() -> SleepConfig {
+        let default = SleepConfig::default();
+        SleepConfig {
+            schedule: Self::read_value("SleepSchedule")
+                .and_then(|v| {
+                    let schedule = SleepSchedule::parse(&v);
+                    if schedule.is_none() {
+                        eprintln!("Invalid sleep schedule '{}', using default", v);
+                    }
+                    schedule
+                })
+                .unwrap_or(default.schedule),
+            idle_seconds: Self::read_value("IdleSeconds")
+                .and_then(|v| v.parse::<u64>().ok())
+                .unwrap_or(default.idle_seconds),
+            idle_percent: Self::read_value("IdlePercent")
+                .and_then(|v| v.parse::<f64>().ok())
+                .filter(|v| (0.0..=100.0).contains(v))
+                .unwrap_or(default.idle_percent),
+        }
+    }
 }
 
 /// Cross-platform system integration trait
 pub trait SystemIntegration {
     fn show_dialog(message: &str, title: &str) -> Result<(), Box<dyn std::error::Error>>;
     fn open_system_monitor() -> Result<(), Box<dyn std::error::Error>>;
-    fn get_local_hour() -> u32;
+    fn get_local_time() -> LocalTime;
 }
 
 /// Platform-specific implementation type aliases
-#[cfg(windows)]
-pub type CpuMonitorImpl = windows::WindowsCpuMonitor;
-#[cfg(target_os = "macos")]
-pub type CpuMonitorImpl = macos::MacosCpuMonitor;
-#[cfg(target_os = "linux")]
-pub type CpuMonitorImpl = linux::LinuxCpuMonitor;
-
 #[cfg(windows)]
 pub type SettingsManagerImpl = windows::WindowsSettingsManager;
 #[cfg(target_os = "macos")]
@@ -53,3 +215,11 @@ pub type SystemIntegrationImpl = windows::WindowsSystemIntegration;
 pub type SystemIntegrationImpl = macos::MacosSystemIntegration;
 #[cfg(target_os = "linux")]
 pub type SystemIntegrationImpl = linux::LinuxSystemIntegration;
+
+/// Metric sources available on this platform, in menu order
+#[cfg(target_os = "linux")]
+pub use linux::metric_sources;
+#[cfg(target_os = "macos")]
+pub use macos::metric_sources;
+#[cfg(windows)]
+pub use windows::metric_sources;
@@ -1,7 +1,7 @@
 use windows::Win32::UI::WindowsAndMessaging::{DispatchMessageW, GetMessageW, TranslateMessage};
 
-use crate::app::App;
-use crate::icon_manager::{IconManager, Theme};
+use crate::app::{App, Overrides};
+use crate::icon_manager::IconManager;
 
 pub struct WindowsApp {
     app: App,
@@ -11,9 +11,10 @@ impl WindowsApp {
     pub fn new(
         icon_manager: IconManager,
         initial_icon: &str,
-        initial_theme: Option<Theme>,
+        overrides: Overrides,
+        show_tray: bool,
     ) -> Result<Self, Box<dyn std::error::Error>> {
-        let app = App::new(icon_manager, initial_icon, initial_theme)?;
+        let app = App::new(icon_manager, initial_icon, overrides, show_tray)?;
         Ok(WindowsApp { app })
     }
 
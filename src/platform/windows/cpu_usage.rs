@@ -1,14 +1,22 @@
-use crate::platform::CpuMonitor;
+use crate::platform::{MetricSource, Reading};
 use std::io;
-use std::sync::Mutex;
 use windows::{Win32::Foundation::FILETIME, Win32::System::Threading::GetSystemTimes};
 
-pub struct WindowsCpuMonitor;
+#[derive(Default)]
+pub struct WindowsCpuMonitor {
+    prev: Option<(f64, f64)>,
+}
+
+impl MetricSource for WindowsCpuMonitor {
+    fn name(&self) -> &str {
+        "cpu"
+    }
 
-static CPU_STATE: Mutex<Option<(f64, f64)>> = Mutex::new(None);
+    fn label(&self) -> &str {
+        "CPU"
+    }
 
-impl CpuMonitor for WindowsCpuMonitor {
-    fn get_cpu_usage() -> io::Result<f64> {
+    fn sample(&mut self) -> io::Result<Reading> {
         let mut idle_time = empty();
         let mut kernel_time = empty();
         let mut user_time = empty();
@@ -25,8 +33,7 @@ impl CpuMonitor for WindowsCpuMonitor {
         let user_time = filetime_to_u64(user_time) as f64;
         let total_time = kernel_time + user_time;
 
-        let mut state = CPU_STATE.lock().unwrap();
-        let usage = if let Some((prev_total, prev_idle)) = *state {
+        let usage = if let Some((prev_total, prev_idle)) = self.prev {
             let total_diff = total_time - prev_total;
             let idle_diff = idle_time - prev_idle;
             if total_diff > 0.0 {
@@ -38,8 +45,11 @@ impl CpuMonitor for WindowsCpuMonitor {
             0.0 // First call, return 0 usage
         };
 
-        *state = Some((total_time, idle_time));
-        Ok(usage)
+        self.prev = Some((total_time, idle_time));
+        Ok(Reading {
+            value: usage,
+            raw: usage,
+        })
     }
 }
 
@@ -0,0 +1,51 @@
+use crate::platform::{MemoryInfo, MetricSource, Reading};
+use std::io;
+use windows::Win32::System::SystemInformation::{GlobalMemoryStatusEx, MEMORYSTATUSEX};
+
+#[derive(Default)]
+pub struct WindowsMemoryMonitor {
+    info: MemoryInfo,
+}
+
+impl MetricSource for WindowsMemoryMonitor {
+    fn name(&self) -> &str {
+        "memory"
+    }
+
+    fn label(&self) -> &str {
+        "Memory"
+    }
+
+    fn sample(&mut self) -> io::Result<Reading> {
+        let mut status = MEMORYSTATUSEX {
+            dwLength: std::mem::size_of::<MEMORYSTATUSEX>() as u32,
+            ..Default::default()
+        };
+        unsafe {
+            GlobalMemoryStatusEx(&mut status)
+                .map_err(|e| io::Error::other(format!("Failed to get memory status: {}", e)))?;
+        }
+
+        // The commit limit ("page file" in this API) is physical memory plus
+        // the paging files, so the difference is what has spilled to disk.
+        let committed = status
+            .ullTotalPageFile
+            .saturating_sub(status.ullAvailPageFile);
+        let resident = status.ullTotalPhys.saturating_sub(status.ullAvailPhys);
+
+        self.info = MemoryInfo {
+            total: status.ullTotalPhys,
+            available: status.ullAvailPhys,
+            swap_total: status.ullTotalPageFile.saturating_sub(status.ullTotalPhys),
+            swap_used: committed.saturating_sub(resident),
+            // Windows does not expose a dirty page counter here
+            dirty: 0,
+        };
+        let value = self.info.usage_percent();
+        Ok(Reading { value, raw: value })
+    }
+
+    fn describe(&self, _reading: &Reading) -> String {
+        self.info.describe()
+    }
+}
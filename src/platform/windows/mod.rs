@@ -1,8 +1,19 @@
 pub mod app;
 pub mod cpu_usage;
+pub mod memory_usage;
 pub mod settings;
 pub mod system_integration;
 
 pub use cpu_usage::WindowsCpuMonitor;
+pub use memory_usage::WindowsMemoryMonitor;
 pub use settings::WindowsSettingsManager;
 pub use system_integration::WindowsSystemIntegration;
+
+use crate::platform::MetricSource;
+
+pub fn metric_sources() -> Vec<Box<dyn MetricSource>> {
+    vec![
+        Box::new(WindowsCpuMonitor::default()),
+        Box::new(WindowsMemoryMonitor::default()),
+    ]
+}
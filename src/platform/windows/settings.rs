@@ -1,4 +1,5 @@
 use crate::icon_manager::{IconManager, Theme};
+use crate::info;
 use crate::platform::SettingsManager;
 use winreg::enums::*;
 use winreg::RegKey;
@@ -157,11 +158,49 @@ impl SettingsManager for WindowsSettingsManager {
                     let _ = write_key.delete_value("IconId");
                 }
 
-                println!(
+                info!(
                     "Migrated from legacy IconId {} to IconName: {}, Theme: {}",
                     old_id, icon_name, theme
                 );
             }
         }
     }
+
+    fn location() -> String {
+        "HKEY_CURRENT_USER\\Software\\RustCat".to_string()
+    }
+
+    fn reset_settings() -> Result<(), String> {
+        let key = RegKey::predef(HKEY_CURRENT_USER);
+        match key.delete_subkey_all("Software\\RustCat") {
+            Err(e) if e.kind() != std::io::ErrorKind::NotFound => Err(format!(
+                "Failed to delete registry key 'Software\\RustCat': {}",
+                e
+            )),
+            _ => Ok(()),
+        }
+    }
+
+    fn read_value(key: &str) -> Option<String> {
+        RegKey::predef(HKEY_CURRENT_USER)
+            .open_subkey_with_flags("Software\\RustCat", KEY_READ)
+            .ok()?
+            .get_value::<String, &str>(key)
+            .ok()
+    }
+
+    fn write_value(key: &str, value: &str) {
+        let hkcu = RegKey::predef(HKEY_CURRENT_USER);
+        let sub_key = if let Ok(sub_key) =
+            hkcu.open_subkey_with_flags("Software\\RustCat", KEY_WRITE | KEY_READ)
+        {
+            sub_key
+        } else {
+            hkcu.create_subkey_with_flags("Software\\RustCat", KEY_WRITE | KEY_READ)
+                .expect("create_subkey_with_flags")
+                .0
+        };
+
+        sub_key.set_value(key, &value).expect("set_value");
+    }
 }
@@ -1,4 +1,5 @@
 use crate::platform::SystemIntegration;
+use crate::schedule::LocalTime;
 use std::process::Command;
 use windows::{core::HSTRING, Win32::{Foundation::HWND, UI::WindowsAndMessaging::{MessageBoxW, MB_OK, MESSAGEBOX_STYLE}, System::SystemInformation::GetLocalTime}};
 
@@ -18,10 +19,15 @@ impl SystemIntegration for WindowsSystemIntegration {
         Ok(())
     }
 
-    fn get_local_hour() -> u32 {
+    fn get_local_time() -> LocalTime {
         unsafe {
             let st = GetLocalTime();
-            st.wHour as u32
+            LocalTime {
+                // wDayOfWeek counts from Sunday = 0
+                weekday: (st.wDayOfWeek as u32 + 6) % 7,
+                hour: st.wHour as u32,
+                minute: st.wMinute as u32,
+            }
         }
     }
 }
@@ -0,0 +1,293 @@
+//! When the cat is allowed to fall asleep.
+//!
+//! A schedule is stored as a short text setting:
+//!
+//! - `never` — never sleep
+//! - `idle` — sleep whenever the CPU is idle, at any time of day
+//! - one or more windows separated by `;`, each an optional day list followed
+//!   by a time range, e.g. `mon-fri 22:00-06:00; sat,sun 00:00-10:00`.
+//!   Without a day list the window applies every day. A window that crosses
+//!   midnight belongs to the day it starts on.
+
+const DAY_NAMES: [&str; 7] = ["mon", "tue", "wed", "thu", "fri", "sat", "sun"];
+
+/// Local wall-clock time
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct LocalTime {
+    /// Day of the week, 0 = Monday .. 6 = Sunday
+    pub weekday: u32,
+    pub hour: u32,
+    pub minute: u32,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SleepWindow {
+    /// Bit N set = window starts on weekday N (0 = Monday)
+    days: u8,
+    /// Minutes since midnight
+    start: u32,
+    end: u32,
+}
+
+impl SleepWindow {
+    fn starts_on(&self, weekday: u32) -> bool {
+        self.days & (1 << weekday) != 0
+    }
+
+    fn contains(&self, time: LocalTime) -> bool {
+        let minute = time.hour * 60 + time.minute;
+        if self.start <= self.end {
+            self.starts_on(time.weekday) && (self.start..self.end).contains(&minute)
+        } else {
+            // Crosses midnight: the evening part belongs to today, the morning
+            // part to the window that started yesterday.
+            let yesterday = (time.weekday + 6) % 7;
+            (self.starts_on(time.weekday) && minute >= self.start)
+                || (self.starts_on(yesterday) && minute < self.end)
+        }
+    }
+
+    fn parse(s: &str) -> Option<Self> {
+        let mut parts = s.split_whitespace();
+        let (days, range) = match (parts.next()?, parts.next(), parts.next()) {
+            (range, None, _) => (0x7f, range),
+            (days, Some(range), None) => (parse_days(days)?, range),
+            _ => return None,
+        };
+        let (start, end) = range.split_once('-')?;
+        Some(Self {
+            days,
+            start: parse_clock(start)?,
+            end: parse_clock(end)?,
+        })
+    }
+}
+
+impl std::fmt::Display for SleepWindow {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if self.days != 0x7f {
+            let days: Vec<&str> = (0..7)
+                .filter(|&d| self.starts_on(d))
+                .map(|d| DAY_NAMES[d as usize])
+                .collect();
+            write!(f, "{} ", days.join(","))?;
+        }
+        write!(
+            f,
+            "{:02}:{:02}-{:02}:{:02}",
+            self.start / 60,
+            self.start % 60,
+            self.end / 60,
+            self.end % 60
+        )
+    }
+}
+
+/// `mon-fri`, `sat,sun`, `mon,wed-fri` or `daily`
+fn parse_days(s: &str) -> Option<u8> {
+    if s.eq_ignore_ascii_case("daily") {
+        return Some(0x7f);
+    }
+    let day = |name: &str| {
+        DAY_NAMES
+            .iter()
+            .position(|d| d.eq_ignore_ascii_case(name))
+            .map(|d| d as u32)
+    };
+    let mut days = 0u8;
+    for item in s.split(',') {
+        match item.split_once('-') {
+            Some((from, to)) => {
+                let (from, to) = (day(from)?, day(to)?);
+                let mut d = from;
+                loop {
+                    days |= 1 << d;
+                    if d == to {
+                        break;
+                    }
+                    d = (d + 1) % 7;
+                }
+            }
+            None => days |= 1 << day(item)?,
+        }
+    }
+    Some(days)
+}
+
+/// `HH:MM` to minutes since midnight. `24:00` is accepted as end of day.
+fn parse_clock(s: &str) -> Option<u32> {
+    let (hour, minute) = s.split_once(':')?;
+    let (hour, minute) = (hour.parse::<u32>().ok()?, minute.parse::<u32>().ok()?);
+    if minute >= 60 || hour > 24 || (hour == 24 && minute != 0) {
+        return None;
+    }
+    Some(hour * 60 + minute)
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SleepSchedule {
+    Never,
+    /// Sleep whenever idle, with no time window
+    Anytime,
+    Windows(Vec<SleepWindow>),
+}
+
+impl SleepSchedule {
+    pub fn parse(s: &str) -> Option<Self> {
+        match s.trim() {
+            "never" => Some(SleepSchedule::Never),
+            "idle" => Some(SleepSchedule::Anytime),
+            windows => windows
+                .split(';')
+                .filter(|w| !w.trim().is_empty())
+                .map(SleepWindow::parse)
+                .collect::<Option<Vec<_>>>()
+                .filter(|w| !w.is_empty())
+                .map(SleepSchedule::Windows),
+        }
+    }
+
+    /// Whether the cat may fall asleep at `time`.
+    pub fn allows(&self, time: LocalTime) -> bool {
+        match self {
+            SleepSchedule::Never => false,
+            SleepSchedule::Anytime => true,
+            SleepSchedule::Windows(windows) => windows.iter().any(|w| w.contains(time)),
+        }
+    }
+}
+
+impl Default for SleepSchedule {
+    /// 22:00–06:00 every day
+    fn default() -> Self {
+        SleepSchedule::Windows(vec![SleepWindow {
+            days: 0x7f,
+            start: 22 * 60,
+            end: 6 * 60,
+        }])
+    }
+}
+
+impl std::fmt::Display for SleepSchedule {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            SleepSchedule::Never => write!(f, "never"),
+            SleepSchedule::Anytime => write!(f, "idle"),
+            SleepSchedule::Windows(windows) => {
+                let windows: Vec<String> = windows.iter().map(|w| w.to_string()).collect();
+                write!(f, "{}", windows.join("; "))
+            }
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct SleepConfig {
+    pub schedule: SleepSchedule,
+    /// How long the CPU has to stay idle before the cat falls asleep
+    pub idle_seconds: u64,
+    /// CPU usage below which it counts as idle, whatever drives the animation
+    pub idle_percent: f64,
+}
+
+impl Default for SleepConfig {
+    fn default() -> Self {
+        Self {
+            schedule: SleepSchedule::default(),
+            idle_seconds: 60,
+            idle_percent: 5.0,
+        }
+    }
+}
+
+/// Manual override from the tray menu, not persisted
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum SleepOverride {
+    #[default]
+    Automatic,
+    ForceSleep,
+    ForceWake,
+}
+
+impl SleepOverride {
+    pub const ALL: [SleepOverride; 3] = [
+        SleepOverride::Automatic,
+        SleepOverride::ForceSleep,
+        SleepOverride::ForceWake,
+    ];
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            SleepOverride::Automatic => "Automatic",
+            SleepOverride::ForceSleep => "Force Sleep",
+            SleepOverride::ForceWake => "Force Wake",
+        }
+    }
+
+    /// Short name for the control socket
+    pub fn name(&self) -> &'static str {
+        match self {
+            SleepOverride::Automatic => "auto",
+            SleepOverride::ForceSleep => "asleep",
+            SleepOverride::ForceWake => "awake",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|mode| mode.name() == name)
+    }
+
+    /// Apply the override to the state the idle detection arrived at.
+    pub fn resolve(&self, automatic: bool) -> bool {
+        match self {
+            SleepOverride::Automatic => automatic,
+            SleepOverride::ForceSleep => true,
+            SleepOverride::ForceWake => false,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn at(weekday: u32, hour: u32, minute: u32) -> LocalTime {
+        LocalTime {
+            weekday,
+            hour,
+            minute,
+        }
+    }
+
+    #[test]
+    fn test_default_matches_legacy_hours() {
+        let schedule = SleepSchedule::default();
+        assert!(schedule.allows(at(2, 23, 0)));
+        assert!(schedule.allows(at(2, 5, 59)));
+        assert!(!schedule.allows(at(2, 6, 0)));
+        assert!(!schedule.allows(at(2, 21, 59)));
+    }
+
+    #[test]
+    fn test_weekday_windows_cross_midnight() {
+        let schedule = SleepSchedule::parse("mon-fri 22:00-06:00; sat,sun 09:00-17:00").unwrap();
+        // Friday night runs into Saturday morning
+        assert!(schedule.allows(at(5, 3, 0)));
+        // Sunday night is not covered, so Monday 03:00 is awake
+        assert!(!schedule.allows(at(0, 3, 0)));
+        assert!(schedule.allows(at(6, 12, 30)));
+        assert_eq!(
+            schedule.to_string(),
+            "mon,tue,wed,thu,fri 22:00-06:00; sat,sun 09:00-17:00"
+        );
+        assert_eq!(SleepSchedule::parse(&schedule.to_string()), Some(schedule));
+    }
+
+    #[test]
+    fn test_parse_rejects_garbage() {
+        assert_eq!(SleepSchedule::parse("idle"), Some(SleepSchedule::Anytime));
+        assert!(SleepSchedule::parse("").is_none());
+        assert!(SleepSchedule::parse("someday 22:00-06:00").is_none());
+        assert!(SleepSchedule::parse("22:00-25:00").is_none());
+    }
+}
@@ -0,0 +1,458 @@
+//! Every user option in one typed struct, stored as a commented TOML file
+//! (`~/.config/rustcat/settings.toml` on Linux).
+//!
+//! Loading is forgiving: invalid values are reported and replaced by their
+//! defaults rather than failing the whole file, so one typo doesn't reset
+//! everything else. Unknown keys are reported too, and written back as they
+//! were, so options from a newer RustCat survive saving.
+
+use crate::badge::Badge;
+use crate::icon_manager::Theme;
+use crate::migrations;
+use crate::schedule::{SleepConfig, SleepSchedule};
+use crate::smoothing::SmoothingConfig;
+use crate::speed_curve::{CurveKind, SpeedCurve};
+use std::collections::BTreeMap;
+use toml::{Table, Value};
+
+/// Error of [`Settings::apply`] for a key it doesn't know
+const UNKNOWN_KEY: &str = "unknown key";
+/// Tables of `settings.toml`
+const SECTIONS: [&str; 3] = ["speed", "smoothing", "sleep"];
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Settings {
+    /// Icon pack name
+    pub icon: String,
+    /// `None` follows the system
+    pub theme: Option<Theme>,
+    /// Name of the metric source driving the animation
+    pub metric: String,
+    /// Corner of the usage percentage drawn on the icon
+    pub badge: Badge,
+    pub speed_curve: SpeedCurve,
+    pub smoothing: SmoothingConfig,
+    pub sleep: SleepConfig,
+    /// Keys this version doesn't know, by section, `""` for the top level
+    pub unknown: BTreeMap<String, Table>,
+}
+
+impl Default for Settings {
+    fn default() -> Self {
+        Self {
+            icon: "cat".to_string(),
+            theme: None,
+            metric: "cpu".to_string(),
+            badge: Badge::Off,
+            speed_curve: SpeedCurve::default(),
+            smoothing: SmoothingConfig::default(),
+            sleep: SleepConfig::default(),
+            unknown: BTreeMap::new(),
+        }
+    }
+}
+
+impl Settings {
+    /// Settings from a parsed, up to date settings document, together with a
+    /// warning for every key that was unknown or had an invalid value. Invalid
+    /// values keep their defaults, unknown keys are kept in [`Self::unknown`].
+    pub fn from_table(table: &Table) -> (Self, Vec<String>) {
+        let mut settings = Settings::default();
+        let mut warnings = Vec::new();
+
+        for (key, value) in table {
+            match (key.as_str(), value) {
+                // Only read by the migrations
+                ("version", _) => {}
+                (section, Value::Table(fields)) if SECTIONS.contains(&section) => {
+                    for (field, value) in fields {
+                        settings.apply_or_keep(section, field, value, &mut warnings);
+                    }
+                }
+                (section, _) if SECTIONS.contains(&section) => {
+                    warnings.push(format!("{}: expected a table, using the defaults", section));
+                }
+                _ => settings.apply_or_keep("", key, value, &mut warnings),
+            }
+        }
+
+        let curve = &mut settings.speed_curve;
+        if curve.min_delay > curve.max_delay {
+            warnings.push(format!(
+                "speed.min_frame_delay ({}) is larger than speed.max_frame_delay ({}), \
+                 using the defaults",
+                curve.min_delay, curve.max_delay
+            ));
+            curve.min_delay = SpeedCurve::DEFAULT_MIN_DELAY;
+            curve.max_delay = SpeedCurve::DEFAULT_MAX_DELAY;
+        }
+        (settings, warnings)
+    }
+
+    /// Apply `key` of `section`, or keep it in [`Self::unknown`] if it isn't
+    /// an option
+    fn apply_or_keep(
+        &mut self,
+        section: &str,
+        key: &str,
+        value: &Value,
+        warnings: &mut Vec<String>,
+    ) {
+        let path = match section {
+            "" => key.to_string(),
+            _ => format!("{}.{}", section, key),
+        };
+        match self.apply(&path, value) {
+            Ok(()) => {}
+            Err(e) if e == UNKNOWN_KEY => {
+                warnings.push(format!("{}: unknown key, kept but not used", path));
+                self.unknown
+                    .entry(section.to_string())
+                    .or_default()
+                    .insert(key.to_string(), value.clone());
+            }
+            Err(e) => warnings.push(format!("{}: {}, using the default", path, e)),
+        }
+    }
+
+    /// Set the option at `path`, e.g. `sleep.idle_seconds`
+    fn apply(&mut self, path: &str, value: &Value) -> Result<(), String> {
+        match path {
+            "icon" => self.icon = non_empty_string(value)?,
+            "theme" => {
+                let name = non_empty_string(value)?;
+                self.theme =
+                    Some(Theme::from_name(&name).ok_or(format!("unknown theme '{}'", name))?);
+            }
+            "metric" => self.metric = non_empty_string(value)?,
+            "badge" => {
+                let name = non_empty_string(value)?;
+                self.badge = Badge::from_name(&name).ok_or(format!("unknown corner '{}'", name))?;
+            }
+            "speed.curve" => {
+                let name = non_empty_string(value)?;
+                self.speed_curve.kind =
+                    CurveKind::from_name(&name).ok_or(format!("unknown curve '{}'", name))?;
+            }
+            "speed.min_frame_delay" => self.speed_curve.min_delay = unsigned(value)?,
+            "speed.max_frame_delay" => self.speed_curve.max_delay = unsigned(value)?,
+            "speed.points" => self.speed_curve.points = curve_points(value)?,
+            "smoothing.time_constant" => {
+                self.smoothing.time_constant = number(value, 0.0, f64::INFINITY)?
+            }
+            "smoothing.median_window" => {
+                self.smoothing.median_window = unsigned(value)?
+                    .max(1)
+                    .try_into()
+                    .map_err(|_| "value is too large".to_string())?
+            }
+            "smoothing.hysteresis" => self.smoothing.hysteresis = number(value, 0.0, 100.0)?,
+            "sleep.schedule" => {
+                let text = non_empty_string(value)?;
+                self.sleep.schedule =
+                    SleepSchedule::parse(&text).ok_or(format!("invalid schedule '{}'", text))?;
+            }
+            "sleep.idle_seconds" => self.sleep.idle_seconds = unsigned(value)?,
+            "sleep.idle_percent" => self.sleep.idle_percent = number(value, 0.0, 100.0)?,
+            _ => return Err(UNKNOWN_KEY.to_string()),
+        }
+        Ok(())
+    }
+
+    /// Apply one `key=value` line of the old `settings.conf` format
+    pub fn apply_legacy(&mut self, key: &str, value: &str) -> Result<(), String> {
+        let path = legacy_path(key).ok_or(UNKNOWN_KEY)?;
+        self.apply(path, &legacy_value(path, value))
+    }
+
+    /// The settings as a `settings.conf` style `(key, value)` list
+    pub fn legacy_values(&self) -> Vec<(&'static str, String)> {
+        let mut values = vec![("IconName", self.icon.clone())];
+        if let Some(theme) = self.theme {
+            values.push(("Theme", theme.to_string()));
+        }
+        values.extend([
+            ("Metric", self.metric.clone()),
+            ("UsageBadge", self.badge.to_string()),
+            ("SpeedCurve", self.speed_curve.kind.to_string()),
+            ("MinFrameDelay", self.speed_curve.min_delay.to_string()),
+            ("MaxFrameDelay", self.speed_curve.max_delay.to_string()),
+            (
+                "SmoothingTimeConstant",
+                self.smoothing.time_constant.to_string(),
+            ),
+            ("MedianWindow", self.smoothing.median_window.to_string()),
+            ("Hysteresis", self.smoothing.hysteresis.to_string()),
+            ("SleepSchedule", self.sleep.schedule.to_string()),
+            ("IdleSeconds", self.sleep.idle_seconds.to_string()),
+            ("IdlePercent", self.sleep.idle_percent.to_string()),
+        ]);
+        if !self.speed_curve.points.is_empty() {
+            let points: Vec<String> = self
+                .speed_curve
+                .points
+                .iter()
+                .map(|(usage, delay)| format!("{}:{}", usage, delay))
+                .collect();
+            values.push(("SpeedCurvePoints", points.join(", ")));
+        }
+        values
+    }
+
+    /// The settings file, with a comment explaining every option
+    pub fn to_toml(&self) -> String {
+        let quote = |s: &str| Value::String(s.to_string()).to_string();
+        let theme = match self.theme {
+            Some(theme) => format!("theme = {}", quote(&theme.to_string())),
+            None => "# theme = \"dark\"".to_string(),
+        };
+        let points: Vec<String> = self
+            .speed_curve
+            .points
+            .iter()
+            .map(|(usage, delay)| format!("[{:?}, {}]", usage, delay))
+            .collect();
+        format!(
+            r#"# RustCat settings. Changes made from the tray menu are written back here.
+
+# Format of this file, used to upgrade it after RustCat updates
+version = {version}
+
+# Icon pack, one of the entries of the Icon menu
+icon = {icon}
+# "light", "dark" or "auto"; leave unset to follow the system
+{theme}
+# What drives the animation: "cpu", "cpu-busiest" or "memory"
+metric = {metric}
+# Usage percentage on the icon: "off", "top-left", "top-right", "bottom-left"
+# or "bottom-right"
+badge = {badge}
+{unknown}
+[speed]
+# "linear", "logarithmic", "exponential", "stepped" or "custom"
+curve = {curve}
+# Frame delay bounds in milliseconds
+min_frame_delay = {min_delay}
+max_frame_delay = {max_delay}
+# [usage, delay] pairs for the custom curve, e.g. [[0, 200], [50, 60], [100, 10]]
+points = [{points}]
+{unknown_speed}
+[smoothing]
+# Seconds for the moving average to catch up with a change; 0 disables it
+time_constant = {time_constant:?}
+# Samples in the median filter that drops one-off spikes; 1 disables it
+median_window = {median_window}
+# Percentage points usage must move past a threshold to change state
+hysteresis = {hysteresis:?}
+{unknown_smoothing}
+[sleep]
+# "never", "idle" (any time) or windows like "mon-fri 22:00-06:00; sat,sun 00:00-10:00"
+schedule = {schedule}
+# How long CPU usage must stay below idle_percent before the cat falls asleep
+idle_seconds = {idle_seconds}
+idle_percent = {idle_percent:?}
+{unknown_sleep}"#,
+            version = migrations::CURRENT_VERSION,
+            icon = quote(&self.icon),
+            theme = theme,
+            metric = quote(&self.metric),
+            badge = quote(&self.badge.to_string()),
+            curve = quote(&self.speed_curve.kind.to_string()),
+            min_delay = self.speed_curve.min_delay,
+            max_delay = self.speed_curve.max_delay,
+            points = points.join(", "),
+            time_constant = self.smoothing.time_constant,
+            median_window = self.smoothing.median_window,
+            hysteresis = self.smoothing.hysteresis,
+            schedule = quote(&self.sleep.schedule.to_string()),
+            idle_seconds = self.sleep.idle_seconds,
+            idle_percent = self.sleep.idle_percent,
+            unknown = self.unknown_lines(""),
+            unknown_speed = self.unknown_lines("speed"),
+            unknown_smoothing = self.unknown_lines("smoothing"),
+            unknown_sleep = self.unknown_lines("sleep"),
+        )
+    }
+
+    /// Unknown keys of `section` as `key = value` lines, tables written inline
+    /// so they stay in the section
+    fn unknown_lines(&self, section: &str) -> String {
+        let Some(table) = self.unknown.get(section) else {
+            return String::new();
+        };
+        let mut lines = String::new();
+        for (key, value) in table {
+            let bare = !key.is_empty()
+                && key
+                    .chars()
+                    .all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-');
+            let key = if bare {
+                key.clone()
+            } else {
+                Value::String(key.clone()).to_string()
+            };
+            lines.push_str(&format!("{} = {}\n", key, value));
+        }
+        lines
+    }
+}
+
+/// Where a `settings.conf` key lives in `settings.toml`
+pub fn legacy_path(key: &str) -> Option<&'static str> {
+    Some(match key {
+        "IconName" => "icon",
+        "Theme" => "theme",
+        "Metric" => "metric",
+        "UsageBadge" => "badge",
+        "SpeedCurve" => "speed.curve",
+        "MinFrameDelay" => "speed.min_frame_delay",
+        "MaxFrameDelay" => "speed.max_frame_delay",
+        "SpeedCurvePoints" => "speed.points",
+        "SmoothingTimeConstant" => "smoothing.time_constant",
+        "MedianWindow" => "smoothing.median_window",
+        "Hysteresis" => "smoothing.hysteresis",
+        "SleepSchedule" => "sleep.schedule",
+        "IdleSeconds" => "sleep.idle_seconds",
+        "IdlePercent" => "sleep.idle_percent",
+        _ => return None,
+    })
+}
+
+/// Typed value for the untyped `settings.conf` text of the option at `path`
+pub fn legacy_value(path: &str, text: &str) -> Value {
+    let is_text = matches!(
+        path,
+        "icon" | "theme" | "metric" | "badge" | "speed.curve" | "speed.points" | "sleep.schedule"
+    );
+    if is_text {
+        return Value::String(text.to_string());
+    }
+    text.parse::<i64>()
+        .map(Value::Integer)
+        .or_else(|_| text.parse::<f64>().map(Value::Float))
+        .unwrap_or_else(|_| Value::String(text.to_string()))
+}
+
+fn non_empty_string(value: &Value) -> Result<String, String> {
+    match value.as_str() {
+        Some(s) if !s.trim().is_empty() => Ok(s.trim().to_string()),
+        Some(_) => Err("must not be empty".to_string()),
+        None => Err(format!("expected a string, found {}", value.type_str())),
+    }
+}
+
+fn unsigned(value: &Value) -> Result<u64, String> {
+    match value.as_integer() {
+        Some(n) => u64::try_from(n).map_err(|_| format!("{} is negative", n)),
+        None => Err(format!("expected an integer, found {}", value.type_str())),
+    }
+}
+
+/// A float in `min..=max`; integers are accepted too
+fn number(value: &Value, min: f64, max: f64) -> Result<f64, String> {
+    let n = match value {
+        Value::Float(n) => *n,
+        Value::Integer(n) => *n as f64,
+        _ => return Err(format!("expected a number, found {}", value.type_str())),
+    };
+    if !(min..=max).contains(&n) {
+        return Err(format!("{} is out of range", n));
+    }
+    Ok(n)
+}
+
+/// `[[usage, delay], ...]`, or the old `"usage:delay, ..."` text
+fn curve_points(value: &Value) -> Result<Vec<(f64, u64)>, String> {
+    if let Some(text) = value.as_str() {
+        return SpeedCurve::parse_points(text).ok_or(format!("invalid points '{}'", text));
+    }
+    let Some(pairs) = value.as_array() else {
+        return Err(format!("expected an array, found {}", value.type_str()));
+    };
+    let mut points = pairs
+        .iter()
+        .map(|pair| match pair.as_array().map(Vec::as_slice) {
+            Some([usage, delay]) => Ok((number(usage, 0.0, 100.0)?, unsigned(delay)?)),
+            _ => Err("expected [usage, delay] pairs".to_string()),
+        })
+        .collect::<Result<Vec<_>, String>>()?;
+    points.sort_by(|a, b| a.0.total_cmp(&b.0));
+    Ok(points)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(text: &str) -> Result<(Settings, Vec<String>), toml::de::Error> {
+        Ok(Settings::from_table(&text.parse()?))
+    }
+
+    #[test]
+    fn test_round_trip() {
+        let mut settings = Settings {
+            icon: "parrot".to_string(),
+            theme: Some(Theme::Dark),
+            metric: "memory".to_string(),
+            badge: Badge::BottomRight,
+            ..Settings::default()
+        };
+        settings.speed_curve.kind = CurveKind::Custom;
+        settings.speed_curve.points = vec![(0.0, 200), (50.5, 60)];
+        settings.sleep.schedule = SleepSchedule::parse("mon-fri 22:00-06:00").unwrap();
+
+        let (parsed, warnings) = parse(&settings.to_toml()).unwrap();
+        assert_eq!(parsed, settings);
+        assert!(warnings.is_empty(), "{:?}", warnings);
+
+        let (parsed, _) = parse(&Settings::default().to_toml()).unwrap();
+        assert_eq!(parsed, Settings::default());
+    }
+
+    #[test]
+    fn test_bad_values_are_reported() {
+        let (settings, warnings) = parse(
+            r#"
+            icon = "parrot"
+            colour = "red"
+            theme = 3
+            [sleep]
+            idle_percent = 150
+            idle_seconds = 30
+            "#,
+        )
+        .unwrap();
+        assert_eq!(settings.icon, "parrot");
+        assert_eq!(settings.theme, None);
+        assert_eq!(settings.sleep.idle_percent, 5.0);
+        assert_eq!(settings.sleep.idle_seconds, 30);
+        assert_eq!(warnings.len(), 3, "{:?}", warnings);
+        assert!(warnings[0].starts_with("colour:"), "{:?}", warnings);
+        // Unknown keys are written back
+        assert!(settings
+            .to_toml()
+            .contains("\nbadge = \"off\"\ncolour = \"red\"\n"));
+
+        assert!(parse("icon = ").is_err());
+    }
+
+    #[test]
+    fn test_legacy_keys() {
+        let mut settings = Settings::default();
+        for (key, value) in [
+            ("IconName", "parrot"),
+            ("Theme", "light"),
+            ("Metric", "cpu-busiest"),
+            ("SpeedCurvePoints", "100:10, 0:200"),
+            ("IdlePercent", "7"),
+        ] {
+            settings.apply_legacy(key, value).unwrap();
+        }
+        assert_eq!(settings.icon, "parrot");
+        assert_eq!(settings.theme, Some(Theme::Light));
+        assert_eq!(settings.metric, "cpu-busiest");
+        assert_eq!(settings.speed_curve.points, [(0.0, 200), (100.0, 10)]);
+        assert_eq!(settings.sleep.idle_percent, 7.0);
+        assert!(settings.apply_legacy("Bogus", "1").is_err());
+    }
+}
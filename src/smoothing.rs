@@ -0,0 +1,130 @@
+//! Smoothing stage between metric sampling and the speed calculation.
+//!
+//! Raw samples are first passed through an optional median-of-N filter, which
+//! drops one-off spikes entirely, and then through an exponential moving
+//! average so the cat changes pace gradually instead of twitching every
+//! second. State changes such as sleep/wake use [`Hysteresis`] on top of the
+//! smoothed signal so they don't flap around a single threshold.
+
+use std::collections::VecDeque;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct SmoothingConfig {
+    /// EMA time constant in seconds; 0 disables the EMA
+    pub time_constant: f64,
+    /// Number of samples for the median filter; 1 disables it
+    pub median_window: usize,
+    /// Width of the hysteresis band for state changes, in percentage points
+    pub hysteresis: f64,
+}
+
+impl Default for SmoothingConfig {
+    fn default() -> Self {
+        Self {
+            time_constant: 2.0,
+            median_window: 1,
+            hysteresis: 2.0,
+        }
+    }
+}
+
+pub struct Smoother {
+    config: SmoothingConfig,
+    window: VecDeque<f64>,
+    ema: Option<f64>,
+}
+
+impl Smoother {
+    pub fn new(config: SmoothingConfig) -> Self {
+        Self {
+            config,
+            window: VecDeque::new(),
+            ema: None,
+        }
+    }
+
+    /// Feed a raw sample taken `dt` seconds after the previous one and return
+    /// the smoothed value.
+    pub fn update(&mut self, raw: f64, dt: f64) -> f64 {
+        let window = self.config.median_window.max(1);
+        self.window.push_back(raw);
+        while self.window.len() > window {
+            self.window.pop_front();
+        }
+        let filtered = median(&self.window);
+
+        let smoothed = match self.ema {
+            Some(prev) if self.config.time_constant > 0.0 => {
+                let alpha = 1.0 - (-dt / self.config.time_constant).exp();
+                prev + alpha * (filtered - prev)
+            }
+            _ => filtered,
+        };
+        self.ema = Some(smoothed);
+        smoothed
+    }
+}
+
+fn median(values: &VecDeque<f64>) -> f64 {
+    let mut sorted: Vec<f64> = values.iter().copied().collect();
+    sorted.sort_by(f64::total_cmp);
+    // The two middle values, or the same one twice for an odd count
+    let len = sorted.len();
+    (sorted[(len - 1) / 2] + sorted[len / 2]) / 2.0
+}
+
+/// Two-threshold switch: turns low once the value drops below `threshold`
+/// and only turns high again once it reaches `threshold + band`.
+pub struct Hysteresis {
+    threshold: f64,
+    band: f64,
+    low: bool,
+}
+
+impl Hysteresis {
+    pub fn new(threshold: f64, band: f64) -> Self {
+        Self {
+            threshold,
+            band: band.max(0.0),
+            low: false,
+        }
+    }
+
+    /// Feed a value and return whether the signal is currently low.
+    pub fn update(&mut self, value: f64) -> bool {
+        if self.low {
+            self.low = value < self.threshold + self.band;
+        } else {
+            self.low = value < self.threshold;
+        }
+        self.low
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_median_drops_single_spike() {
+        let mut smoother = Smoother::new(SmoothingConfig {
+            time_constant: 0.0,
+            median_window: 3,
+            hysteresis: 0.0,
+        });
+        assert_eq!(smoother.update(10.0, 1.0), 10.0);
+        assert_eq!(smoother.update(12.0, 1.0), 11.0);
+        assert_eq!(smoother.update(95.0, 1.0), 12.0);
+        assert_eq!(smoother.update(11.0, 1.0), 12.0);
+    }
+
+    #[test]
+    fn test_hysteresis_band() {
+        let mut idle = Hysteresis::new(5.0, 2.0);
+        assert!(!idle.update(6.0));
+        assert!(idle.update(4.0));
+        assert!(idle.update(6.5));
+        assert!(!idle.update(7.0));
+        assert!(!idle.update(6.0));
+    }
+}
@@ -0,0 +1,188 @@
+//! Mapping from metric usage to animation frame delay.
+//!
+//! Every curve maps usage (0–100) to an intensity `f` in 0–1 and the frame
+//! delay is `min_delay / f`, clamped to `[min_delay, max_delay]`. With the
+//! default 10/200 ms bounds the linear curve is exactly the original
+//! `200 / clamp(usage / 5, 1, 20)` formula.
+
+/// Shape of the usage → speed mapping
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CurveKind {
+    /// Frame rate proportional to usage
+    Linear,
+    /// Speeds up quickly at low usage, flattens out towards 100%
+    Logarithmic,
+    /// Stays slow at low usage, only runs flat out close to 100%
+    Exponential,
+    /// Linear, quantized to 20% wide steps
+    Stepped,
+    /// User-defined `(usage, delay)` table, interpolated linearly
+    Custom,
+}
+
+impl CurveKind {
+    pub const ALL: [CurveKind; 5] = [
+        CurveKind::Linear,
+        CurveKind::Logarithmic,
+        CurveKind::Exponential,
+        CurveKind::Stepped,
+        CurveKind::Custom,
+    ];
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "linear" => Some(CurveKind::Linear),
+            "logarithmic" => Some(CurveKind::Logarithmic),
+            "exponential" => Some(CurveKind::Exponential),
+            "stepped" => Some(CurveKind::Stepped),
+            "custom" => Some(CurveKind::Custom),
+            _ => None,
+        }
+    }
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            CurveKind::Linear => "Linear",
+            CurveKind::Logarithmic => "Logarithmic",
+            CurveKind::Exponential => "Exponential",
+            CurveKind::Stepped => "Stepped",
+            CurveKind::Custom => "Custom",
+        }
+    }
+}
+
+impl std::fmt::Display for CurveKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            CurveKind::Linear => write!(f, "linear"),
+            CurveKind::Logarithmic => write!(f, "logarithmic"),
+            CurveKind::Exponential => write!(f, "exponential"),
+            CurveKind::Stepped => write!(f, "stepped"),
+            CurveKind::Custom => write!(f, "custom"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct SpeedCurve {
+    pub kind: CurveKind,
+    /// Fastest frame delay in milliseconds
+    pub min_delay: u64,
+    /// Slowest frame delay in milliseconds
+    pub max_delay: u64,
+    /// `(usage, delay)` points for [`CurveKind::Custom`], sorted by usage
+    pub points: Vec<(f64, u64)>,
+}
+
+impl SpeedCurve {
+    pub const DEFAULT_MIN_DELAY: u64 = 10;
+    pub const DEFAULT_MAX_DELAY: u64 = 200;
+
+    /// Frame delay in milliseconds for a usage percentage.
+    pub fn frame_delay(&self, usage: f64) -> u64 {
+        let min = self.min_delay.max(1) as f64;
+        let max = (self.max_delay as f64).max(min);
+        let t = (usage / 100.0).clamp(0.0, 1.0);
+
+        let delay = match self.kind {
+            CurveKind::Custom if !self.points.is_empty() => interpolate(&self.points, usage),
+            kind => {
+                let f = match kind {
+                    CurveKind::Logarithmic => (1.0 + 9.0 * t).log10(),
+                    CurveKind::Exponential => (10f64.powf(t) - 1.0) / 9.0,
+                    CurveKind::Stepped => (t * 5.0).floor() / 5.0,
+                    // Custom without points behaves like linear
+                    CurveKind::Linear | CurveKind::Custom => t,
+                };
+                if f > 0.0 {
+                    min / f
+                } else {
+                    max
+                }
+            }
+        };
+        delay.clamp(min, max).round() as u64
+    }
+
+    /// Parse a custom curve table such as `0:200, 50:60, 100:10`.
+    pub fn parse_points(s: &str) -> Option<Vec<(f64, u64)>> {
+        let mut points = s
+            .split(',')
+            .filter(|p| !p.trim().is_empty())
+            .map(|p| {
+                let (usage, delay) = p.split_once(':')?;
+                let usage = usage.trim().parse::<f64>().ok()?;
+                let delay = delay.trim().parse::<u64>().ok()?;
+                (0.0..=100.0).contains(&usage).then_some((usage, delay))
+            })
+            .collect::<Option<Vec<_>>>()?;
+        if points.is_empty() {
+            return None;
+        }
+        points.sort_by(|a, b| a.0.total_cmp(&b.0));
+        Some(points)
+    }
+}
+
+impl Default for SpeedCurve {
+    fn default() -> Self {
+        Self {
+            kind: CurveKind::Linear,
+            min_delay: Self::DEFAULT_MIN_DELAY,
+            max_delay: Self::DEFAULT_MAX_DELAY,
+            points: Vec::new(),
+        }
+    }
+}
+
+/// Piecewise-linear lookup in a sorted `(usage, delay)` table. Usage outside
+/// the table takes the delay of the nearest end point.
+fn interpolate(points: &[(f64, u64)], usage: f64) -> f64 {
+    let (first, last) = (points[0], points[points.len() - 1]);
+    if usage <= first.0 {
+        return first.1 as f64;
+    }
+    if usage >= last.0 {
+        return last.1 as f64;
+    }
+    for pair in points.windows(2) {
+        let ((u0, d0), (u1, d1)) = (pair[0], pair[1]);
+        if usage <= u1 {
+            let t = if u1 > u0 {
+                (usage - u0) / (u1 - u0)
+            } else {
+                1.0
+            };
+            return d0 as f64 + t * (d1 as f64 - d0 as f64);
+        }
+    }
+    last.1 as f64
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_linear_matches_legacy_formula() {
+        let curve = SpeedCurve::default();
+        for usage in [0.0, 3.0, 5.0, 12.5, 40.0, 73.0, 100.0] {
+            let legacy = (200.0 / (usage / 5.0_f64).clamp(1.0, 20.0)).round() as u64;
+            assert_eq!(curve.frame_delay(usage), legacy, "usage {}", usage);
+        }
+    }
+
+    #[test]
+    fn test_custom_points() {
+        let curve = SpeedCurve {
+            kind: CurveKind::Custom,
+            points: SpeedCurve::parse_points("100:10, 0:200,50:100").unwrap(),
+            ..Default::default()
+        };
+        assert_eq!(curve.frame_delay(0.0), 200);
+        assert_eq!(curve.frame_delay(25.0), 150);
+        assert_eq!(curve.frame_delay(75.0), 55);
+        assert!(SpeedCurve::parse_points("0:200,150:10").is_none());
+        assert!(SpeedCurve::parse_points("").is_none());
+    }
+}
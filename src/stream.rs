@@ -0,0 +1,224 @@
+//! Status-bar output for desktops without a tray.
+//!
+//! `rust_cat stream` runs the usual animation and metric pipeline without a
+//! tray icon and writes every frame to stdout instead, for waybar custom
+//! modules, i3bar/swaybar, and i3blocks or polybar scripts. Frames are drawn
+//! as glyphs, so the spinner still speeds up with the load.
+
+use crate::app::FrameTick;
+use crate::icon_manager::AnimationState;
+use std::io::{self, Write};
+use std::sync::mpsc::Receiver;
+use std::time::{Duration, Instant};
+
+/// Bars redraw on every line, so faster frames are dropped
+const MIN_INTERVAL: Duration = Duration::from_millis(100);
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum Format {
+    /// One line of text per frame
+    #[default]
+    Plain,
+    /// waybar `return-type: json` objects
+    Waybar,
+    /// The i3bar/swaybar protocol
+    I3bar,
+}
+
+impl Format {
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "plain" => Some(Format::Plain),
+            "waybar" => Some(Format::Waybar),
+            "i3bar" => Some(Format::I3bar),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum Glyphs {
+    /// Emoji and braille, available in most fonts
+    #[default]
+    Unicode,
+    /// Material Design icons from a Nerd Font
+    Nerd,
+}
+
+impl Glyphs {
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "unicode" => Some(Glyphs::Unicode),
+            "nerd" => Some(Glyphs::Nerd),
+            _ => None,
+        }
+    }
+
+    /// Symbol in front of the animation, and the glyphs the state's frames
+    /// map onto
+    fn for_state(self, state: AnimationState) -> (&'static str, &'static [&'static str]) {
+        const BRAILLE_ORBIT: &[&str] = &["⠁", "⠂", "⠄", "⡀", "⢀", "⠠", "⠐", "⠈"];
+        // nf-md-circle_slice_1 to 8
+        const CIRCLE_SLICES: &[&str] = &[
+            "\u{f0a9e}",
+            "\u{f0a9f}",
+            "\u{f0aa0}",
+            "\u{f0aa1}",
+            "\u{f0aa2}",
+            "\u{f0aa3}",
+            "\u{f0aa4}",
+            "\u{f0aa5}",
+        ];
+        match (self, state) {
+            (Glyphs::Unicode, AnimationState::Running) => ("🐈", BRAILLE_ORBIT),
+            (Glyphs::Unicode, AnimationState::Sleeping) => ("🐈", &["z", "zz", "zzz"]),
+            (Glyphs::Unicode, AnimationState::Alert) => ("🙀", BRAILLE_ORBIT),
+            // nf-md-cat
+            (Glyphs::Nerd, AnimationState::Running) => ("\u{f011b}", CIRCLE_SLICES),
+            // nf-md-sleep
+            (Glyphs::Nerd, AnimationState::Sleeping) => ("\u{f011b}", &["\u{f04b2}"]),
+            // nf-md-fire
+            (Glyphs::Nerd, AnimationState::Alert) => ("\u{f0238}", CIRCLE_SLICES),
+        }
+    }
+
+    /// Text for a frame. Packs and glyph sets have different frame counts,
+    /// so the frame's position is scaled onto the glyphs.
+    fn render(self, tick: &FrameTick) -> String {
+        let (symbol, frames) = self.for_state(tick.activity.state);
+        let frame = frames[tick.index * frames.len() / tick.count.max(1) % frames.len()];
+        format!("{} {} {:.0}%", symbol, frame, tick.activity.usage)
+    }
+}
+
+/// Write `frames` to `out` until the animation stops or `out` is closed
+pub fn write_stream(
+    frames: Receiver<FrameTick>,
+    format: Format,
+    glyphs: Glyphs,
+    mut out: impl Write,
+) -> io::Result<()> {
+    if format == Format::I3bar {
+        // Header, then an endless array of status lines
+        writeln!(out, "{{\"version\":1}}")?;
+        writeln!(out, "[")?;
+    }
+    let mut first = true;
+    let mut last_line = String::new();
+    let mut last_write: Option<Instant> = None;
+    for tick in frames {
+        if last_write.is_some_and(|time| time.elapsed() < MIN_INTERVAL) {
+            continue;
+        }
+        let line = format_line(format, glyphs, &tick, first);
+        if line == last_line {
+            continue;
+        }
+        writeln!(out, "{}", line)?;
+        out.flush()?;
+        first = false;
+        last_line = line;
+        last_write = Some(Instant::now());
+    }
+    Ok(())
+}
+
+fn format_line(format: Format, glyphs: Glyphs, tick: &FrameTick, first: bool) -> String {
+    let text = glyphs.render(tick);
+    let activity = &tick.activity;
+    match format {
+        Format::Plain => text,
+        Format::Waybar => format!(
+            "{{\"text\":{},\"tooltip\":{},\"class\":\"{}\",\"percentage\":{:.0}}}",
+            json_string(&text),
+            json_string(&activity.tooltip),
+            activity.state,
+            activity.usage
+        ),
+        Format::I3bar => format!(
+            "{}[{{\"name\":\"rustcat\",\"full_text\":{},\"urgent\":{}}}]",
+            if first { "" } else { "," },
+            json_string(&text),
+            activity.state == AnimationState::Alert
+        ),
+    }
+}
+
+fn json_string(s: &str) -> String {
+    let mut out = String::with_capacity(s.len() + 2);
+    out.push('"');
+    for c in s.chars() {
+        match c {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            '\n' => out.push_str("\\n"),
+            c if c.is_control() => out.push_str(&format!("\\u{:04x}", c as u32)),
+            c => out.push(c),
+        }
+    }
+    out.push('"');
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::app::Activity;
+    use crate::icon_manager::Theme;
+    use std::sync::mpsc;
+
+    fn tick(index: usize, state: AnimationState, usage: f64) -> FrameTick {
+        FrameTick {
+            icon: "cat".to_string(),
+            theme: Theme::Dark,
+            index,
+            count: 5,
+            activity: Activity {
+                usage,
+                state,
+                tooltip: "CPU Usage: \"busy\"".to_string(),
+            },
+        }
+    }
+
+    fn stream(format: Format, ticks: Vec<FrameTick>) -> String {
+        let (sender, receiver) = mpsc::channel();
+        for tick in ticks {
+            sender.send(tick).unwrap();
+        }
+        drop(sender);
+        let mut out = Vec::new();
+        write_stream(receiver, format, Glyphs::Unicode, &mut out).unwrap();
+        String::from_utf8(out).unwrap()
+    }
+
+    #[test]
+    fn test_glyphs_follow_frames() {
+        let texts: Vec<String> = (0..5)
+            .map(|index| Glyphs::Unicode.render(&tick(index, AnimationState::Running, 12.3)))
+            .collect();
+        assert_eq!(
+            texts,
+            ["🐈 ⠁ 12%", "🐈 ⠂ 12%", "🐈 ⡀ 12%", "🐈 ⢀ 12%", "🐈 ⠐ 12%"]
+        );
+        assert_eq!(
+            Glyphs::Nerd.render(&tick(4, AnimationState::Sleeping, 0.0)),
+            "\u{f011b} \u{f04b2} 0%"
+        );
+    }
+
+    #[test]
+    fn test_formats() {
+        let ticks = || vec![tick(0, AnimationState::Alert, 97.0)];
+        assert_eq!(stream(Format::Plain, ticks()), "🙀 ⠁ 97%\n");
+        assert_eq!(
+            stream(Format::Waybar, ticks()),
+            "{\"text\":\"🙀 ⠁ 97%\",\"tooltip\":\"CPU Usage: \\\"busy\\\"\",\
+             \"class\":\"alert\",\"percentage\":97}\n"
+        );
+        assert_eq!(
+            stream(Format::I3bar, ticks()),
+            "{\"version\":1}\n[\n[{\"name\":\"rustcat\",\"full_text\":\"🙀 ⠁ 97%\",\"urgent\":true}]\n"
+        );
+    }
+}
@@ -0,0 +1,319 @@
+//! The cat drawn in a terminal.
+//!
+//! `rust_cat tui` runs the animation without a tray icon and draws each frame
+//! on stdout, with the current reading underneath. Useful over SSH on
+//! machines without a desktop, and for watching the animation engine work
+//! without a tray. Frames are the pack's own ICO images, sent with the kitty
+//! graphics protocol, as sixels, or as ANSI half blocks that any truecolor
+//! terminal can show.
+
+use crate::app::{AppStatus, FrameTick};
+use crate::icon_frame::RgbaImage;
+use crate::icon_manager::{AnimationState, Theme};
+use std::collections::HashMap;
+use std::env;
+use std::io::{self, Write};
+use std::sync::mpsc::Receiver;
+
+/// Terminal cells the kitty renderer scales a frame to, about square
+const KITTY_COLUMNS: u32 = 16;
+const KITTY_ROWS: u32 = 8;
+/// Sixel frames are scaled up to about this many pixels across
+const SIXEL_SIZE: u32 = 128;
+/// Pixels at or above this alpha are drawn, the rest is left transparent
+const ALPHA_CUTOFF: u8 = 128;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Renderer {
+    /// kitty graphics protocol: kitty, WezTerm, Ghostty
+    Kitty,
+    /// DEC sixel graphics: foot, mlterm, xterm -ti vt340
+    Sixel,
+    /// Upper and lower half blocks in 24-bit color
+    Blocks,
+}
+
+impl Renderer {
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "kitty" => Some(Renderer::Kitty),
+            "sixel" => Some(Renderer::Sixel),
+            "blocks" => Some(Renderer::Blocks),
+            _ => None,
+        }
+    }
+
+    /// Best renderer the terminal announces support for. Terminals can't be
+    /// queried without switching them to raw mode, so this goes by the
+    /// variables they set, which `TERM` at least survives SSH.
+    pub fn detect() -> Self {
+        let term = env::var("TERM").unwrap_or_default();
+        let program = env::var("TERM_PROGRAM").unwrap_or_default();
+        if term == "xterm-kitty"
+            || term == "xterm-ghostty"
+            || env::var_os("KITTY_WINDOW_ID").is_some()
+            || matches!(program.as_str(), "WezTerm" | "ghostty")
+        {
+            Renderer::Kitty
+        } else if term.contains("sixel") || term.starts_with("foot") || term.starts_with("mlterm") {
+            Renderer::Sixel
+        } else {
+            Renderer::Blocks
+        }
+    }
+}
+
+/// Draw `frames` to `out` until the animation stops or `out` is closed
+pub fn write_frames(
+    frames: Receiver<FrameTick>,
+    status: AppStatus,
+    renderer: Renderer,
+    mut out: impl Write,
+) -> io::Result<()> {
+    // Encoded frames, which only change with the icon, theme or state
+    let mut drawn: HashMap<(String, Theme, AnimationState, usize), String> = HashMap::new();
+    write!(out, "\x1b[2J")?;
+    for tick in frames {
+        let state = tick.activity.state;
+        let key = (tick.icon.clone(), tick.theme, state, tick.index);
+        if !drawn.contains_key(&key) {
+            let frame = status
+                .icon_manager()
+                .get_state_frames(&tick.icon, state, Some(tick.theme))
+                .and_then(|frames| frames.get(tick.index))
+                .ok_or_else(|| {
+                    io::Error::other(format!("no frame {} of {}", tick.index, tick.icon))
+                })?;
+            drawn.insert(key.clone(), render(renderer, frame.largest()));
+        }
+        // Redraw in place, the reading on the line below the cat
+        write!(
+            out,
+            "\x1b[H{}\r\n{} | {} | {} ({})\x1b[K\r\nPress Enter to quit\x1b[K\x1b[J",
+            drawn[&key], tick.activity.tooltip, state, tick.icon, tick.theme
+        )?;
+        out.flush()?;
+    }
+    Ok(())
+}
+
+/// Remove what the renderer left behind, once drawing stopped
+pub fn finish(renderer: Renderer, mut out: impl Write) -> io::Result<()> {
+    if renderer == Renderer::Kitty {
+        // The image outlives the program otherwise
+        write!(out, "\x1b_Ga=d,d=I,i=1,q=2\x1b\\")?;
+    }
+    write!(out, "\x1b[H\x1b[J")?;
+    out.flush()
+}
+
+fn render(renderer: Renderer, image: &RgbaImage) -> String {
+    match renderer {
+        Renderer::Kitty => render_kitty(image),
+        Renderer::Sixel => render_sixel(image),
+        Renderer::Blocks => render_blocks(image),
+    }
+}
+
+/// Transmit and show the image, replacing the previous frame's, which shares
+/// its image and placement ids. The terminal scales it to the cell area.
+fn render_kitty(image: &RgbaImage) -> String {
+    // Payloads are sent in chunks of at most 4096 base64 bytes
+    let data = base64(&image.pixels);
+    let chunks: Vec<&str> = data
+        .as_bytes()
+        .chunks(4096)
+        .map(|chunk| std::str::from_utf8(chunk).unwrap_or_default())
+        .collect();
+    let mut out = String::with_capacity(data.len() + 128);
+    for (i, chunk) in chunks.iter().enumerate() {
+        let more = u8::from(i + 1 < chunks.len());
+        if i == 0 {
+            out.push_str(&format!(
+                "\x1b_Ga=T,f=32,s={},v={},i=1,p=1,c={},r={},q=2,m={};{}\x1b\\",
+                image.width, image.height, KITTY_COLUMNS, KITTY_ROWS, more, chunk
+            ));
+        } else {
+            out.push_str(&format!("\x1b_Gm={};{}\x1b\\", more, chunk));
+        }
+    }
+    out
+}
+
+/// Sixel image with a 6×6×6 color cube palette, scaled up by whole pixels.
+/// Transparent pixels are left unpainted.
+fn render_sixel(image: &RgbaImage) -> String {
+    let scale = (SIXEL_SIZE / image.width.max(1)).max(1);
+    let (width, height) = (image.width * scale, image.height * scale);
+    let color = |x: u32, y: u32| {
+        let [r, g, b, a] = image.pixel(x / scale, y / scale);
+        let level = |c: u8| (c as u32 * 5 + 127) / 255;
+        (a >= ALPHA_CUTOFF).then(|| level(r) * 36 + level(g) * 6 + level(b))
+    };
+
+    // P2=1: pixels left at 0 keep the terminal's background
+    let mut out = format!("\x1bP0;1;0q\"1;1;{};{}", width, height);
+    let mut used: Vec<u32> = (0..image.height)
+        .flat_map(|y| (0..image.width).filter_map(move |x| color(x * scale, y * scale)))
+        .collect();
+    used.sort_unstable();
+    used.dedup();
+    for index in used {
+        let percent = |level: u32| level * 100 / 5;
+        out.push_str(&format!(
+            "#{};2;{};{};{}",
+            index,
+            percent(index / 36),
+            percent(index / 6 % 6),
+            percent(index % 6)
+        ));
+    }
+    for band in (0..height).step_by(6) {
+        let rows = band..(band + 6).min(height);
+        let mut colors: Vec<u32> = (0..width)
+            .flat_map(|x| rows.clone().filter_map(move |y| color(x, y)))
+            .collect();
+        colors.sort_unstable();
+        colors.dedup();
+        for (i, &index) in colors.iter().enumerate() {
+            if i > 0 {
+                out.push('$'); // Back to the start of the band
+            }
+            out.push_str(&format!("#{}", index));
+            let sixels = (0..width).map(|x| {
+                let bits = rows
+                    .clone()
+                    .filter(|&y| color(x, y) == Some(index))
+                    .fold(0, |bits, y| bits | 1 << (y - band));
+                (63 + bits) as u8 as char
+            });
+            push_run_length(&mut out, sixels);
+        }
+        out.push('-');
+    }
+    out.push_str("\x1b\\");
+    out
+}
+
+/// Sixel data with runs of four or more written as `!<count><sixel>`
+fn push_run_length(out: &mut String, sixels: impl Iterator<Item = char>) {
+    let mut run: Option<(char, usize)> = None;
+    let flush = |out: &mut String, (sixel, count): (char, usize)| {
+        if count >= 4 {
+            out.push_str(&format!("!{}{}", count, sixel));
+        } else {
+            out.extend(std::iter::repeat_n(sixel, count));
+        }
+    };
+    for sixel in sixels {
+        run = match run {
+            Some((current, count)) if current == sixel => Some((current, count + 1)),
+            Some(previous) => {
+                flush(out, previous);
+                Some((sixel, 1))
+            }
+            None => Some((sixel, 1)),
+        };
+    }
+    if let Some(run) = run {
+        flush(out, run);
+    }
+}
+
+/// Two pixels per cell: the upper one as the foreground of `▀`, the lower
+/// one as its background, or `▄` when only the lower one is drawn
+fn render_blocks(image: &RgbaImage) -> String {
+    let mut out = String::new();
+    for y in (0..image.height).step_by(2) {
+        if y > 0 {
+            out.push_str("\r\n");
+        }
+        for x in 0..image.width {
+            let top = image.pixel(x, y);
+            let bottom = if y + 1 < image.height {
+                image.pixel(x, y + 1)
+            } else {
+                [0; 4]
+            };
+            let cell = match (top[3] >= ALPHA_CUTOFF, bottom[3] >= ALPHA_CUTOFF) {
+                (true, true) => format!(
+                    "\x1b[38;2;{};{};{};48;2;{};{};{}m▀",
+                    top[0], top[1], top[2], bottom[0], bottom[1], bottom[2]
+                ),
+                (true, false) => format!("\x1b[38;2;{};{};{};49m▀", top[0], top[1], top[2]),
+                (false, true) => {
+                    format!("\x1b[38;2;{};{};{};49m▄", bottom[0], bottom[1], bottom[2])
+                }
+                (false, false) => "\x1b[0m ".to_string(),
+            };
+            out.push_str(&cell);
+        }
+        out.push_str("\x1b[0m");
+    }
+    out
+}
+
+fn base64(data: &[u8]) -> String {
+    const ALPHABET: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+    let mut out = String::with_capacity(data.len().div_ceil(3) * 4);
+    for chunk in data.chunks(3) {
+        let bytes = [
+            chunk[0],
+            *chunk.get(1).unwrap_or(&0),
+            *chunk.get(2).unwrap_or(&0),
+        ];
+        let n = u32::from_be_bytes([0, bytes[0], bytes[1], bytes[2]]);
+        for i in 0..4 {
+            if i <= chunk.len() {
+                out.push(ALPHABET[(n >> (18 - 6 * i) & 63) as usize] as char);
+            } else {
+                out.push('=');
+            }
+        }
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn image(pixels: &[[u8; 4]], width: u32) -> RgbaImage {
+        RgbaImage {
+            width,
+            height: pixels.len() as u32 / width,
+            pixels: pixels.concat(),
+        }
+    }
+
+    #[test]
+    fn test_blocks_and_sixels() {
+        const RED: [u8; 4] = [255, 0, 0, 255];
+        const CLEAR: [u8; 4] = [0, 0, 0, 0];
+        // 2x2: red over red, then nothing over red
+        let image = image(&[RED, CLEAR, RED, RED], 2);
+        assert_eq!(
+            render_blocks(&image),
+            "\x1b[38;2;255;0;0;48;2;255;0;0m▀\x1b[38;2;255;0;0;49m▄\x1b[0m"
+        );
+
+        let sixel = render_sixel(&image);
+        assert!(sixel.starts_with("\x1bP0;1;0q\"1;1;128;128#180;2;100;0;0#180"));
+        // Red is #180 in the cube, 64 pixels across then 64 left unpainted
+        assert!(sixel.contains("#180!64~!64?-"));
+        assert!(sixel.ends_with("-\x1b\\"));
+    }
+
+    #[test]
+    fn test_kitty_chunks() {
+        assert_eq!(base64(b"cat"), "Y2F0");
+        assert_eq!(base64(b"cats"), "Y2F0cw==");
+
+        let image = image(&[[1, 2, 3, 4]; 32 * 32], 32);
+        let out = render_kitty(&image);
+        // 4096 bytes make 5464 base64 bytes, sent as two chunks
+        assert!(out.starts_with("\x1b_Ga=T,f=32,s=32,v=32,i=1,p=1,c=16,r=8,q=2,m=1;AQIDBA"));
+        assert_eq!(out.matches("\x1b_G").count(), 2);
+        assert!(out.contains("\x1b_Gm=0;"));
+    }
+}
@@ -0,0 +1,217 @@
+//! Modes whose stdout is their output keep every log line off it, so a bar
+//! or terminal reading it never sees anything else.
+
+use std::fs;
+use std::io::Write;
+use std::iter::Peekable;
+use std::path::PathBuf;
+use std::process::{Child, Command, Stdio};
+use std::str::Chars;
+use std::thread;
+use std::time::Duration;
+
+/// Settings written by an older RustCat, which get upgraded with a notice
+fn old_settings(name: &str) -> PathBuf {
+    let dir = std::env::temp_dir().join(format!("rustcat-{}-{}", name, std::process::id()));
+    fs::create_dir_all(&dir).unwrap();
+    let path = dir.join("settings.toml");
+    fs::copy("tests/fixtures/settings/v1-unversioned.toml", &path).unwrap();
+    path
+}
+
+/// Start rust_cat with debug logging opted into, and give it a few seconds
+/// to sample and draw
+fn run(args: &[&str]) -> Child {
+    let child = Command::new(env!("CARGO_BIN_EXE_rust_cat"))
+        .args(args)
+        .env("RUSTCAT_DEBUG", "1")
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .unwrap();
+    thread::sleep(Duration::from_secs(3));
+    child
+}
+
+/// Quit `tui` the way a user would, and return its stdout and stderr
+fn press_enter(mut child: Child) -> (String, String) {
+    child.stdin.take().unwrap().write_all(b"\n").unwrap();
+    let output = child.wait_with_output().unwrap();
+    assert!(output.status.success());
+    (
+        String::from_utf8(output.stdout).unwrap(),
+        String::from_utf8(output.stderr).unwrap(),
+    )
+}
+
+#[test]
+fn test_stream_stdout_is_json() {
+    let settings = old_settings("stream-stdout");
+    let config = settings.to_str().unwrap();
+    let mut child = run(&["stream", "--format", "waybar", "--config", config]);
+    child.kill().unwrap();
+    let output = child.wait_with_output().unwrap();
+    fs::remove_dir_all(settings.parent().unwrap()).unwrap();
+
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    let stderr = String::from_utf8(output.stderr).unwrap();
+    assert!(!stdout.is_empty(), "nothing streamed, stderr: {}", stderr);
+    for line in stdout.lines() {
+        assert!(is_json(line), "not JSON: {:?}", line);
+    }
+    // The logs were written, just not to stdout
+    assert!(stderr.contains("Upgrading"), "stderr: {}", stderr);
+    assert!(stderr.contains("smoothed"), "stderr: {}", stderr);
+}
+
+#[test]
+fn test_tui_stdout_is_drawing() {
+    let settings = old_settings("tui-stdout");
+    let config = settings.to_str().unwrap();
+    let child = run(&["tui", "--renderer", "blocks", "--debug", "--config", config]);
+    let (stdout, stderr) = press_enter(child);
+    fs::remove_dir_all(settings.parent().unwrap()).unwrap();
+
+    // Each frame is drawn from the top left
+    let text = strip_escapes(&stdout.replace("\x1b[H", "\r\n"));
+    // Every line is the cat in half blocks, the reading below it, or the hint
+    let lines: Vec<&str> = text.split("\r\n").collect();
+    assert!(lines.len() > 1, "nothing drawn, stderr: {}", stderr);
+    for line in lines {
+        assert!(
+            line.chars().all(|c| matches!(c, '▀' | '▄' | ' '))
+                || line.ends_with(" | parrot (dark)")
+                || line == "Press Enter to quit",
+            "not drawing: {:?}",
+            line
+        );
+    }
+    assert!(stderr.contains("Upgrading"), "stderr: {}", stderr);
+    assert!(stderr.contains("smoothed"), "stderr: {}", stderr);
+}
+
+#[test]
+fn test_tui_logs_only_with_debug() {
+    let settings = old_settings("tui-quiet");
+    let config = settings.to_str().unwrap();
+    let (_, stderr) = press_enter(run(&["tui", "--renderer", "blocks", "--config", config]));
+    fs::remove_dir_all(settings.parent().unwrap()).unwrap();
+
+    // Notices still show, debug logs don't, despite RUSTCAT_DEBUG
+    assert!(stderr.contains("Upgrading"), "stderr: {}", stderr);
+    assert!(!stderr.contains("smoothed"), "stderr: {}", stderr);
+}
+
+/// `text` without its CSI escape sequences
+fn strip_escapes(text: &str) -> String {
+    let mut out = String::new();
+    let mut chars = text.chars();
+    while let Some(c) = chars.next() {
+        if c == '\x1b' {
+            assert_eq!(chars.next(), Some('['), "unexpected escape in {:?}", text);
+            // Parameters up to the final byte
+            for c in chars.by_ref() {
+                if ('@'..='~').contains(&c) {
+                    break;
+                }
+            }
+        } else {
+            out.push(c);
+        }
+    }
+    out
+}
+
+/// Whether `text` is a single well-formed JSON value
+fn is_json(text: &str) -> bool {
+    let mut chars = text.chars().peekable();
+    json_value(&mut chars) && chars.next().is_none()
+}
+
+fn json_value(chars: &mut Peekable<Chars>) -> bool {
+    skip_whitespace(chars);
+    let valid = match chars.peek() {
+        Some('{') => {
+            chars.next();
+            json_members(chars, '}', true)
+        }
+        Some('[') => {
+            chars.next();
+            json_members(chars, ']', false)
+        }
+        Some('"') => json_string(chars),
+        Some(c) if *c == '-' || c.is_ascii_digit() => {
+            let mut number = String::new();
+            while let Some(c) = chars.next_if(|c| c.is_ascii_digit() || "+-.eE".contains(*c)) {
+                number.push(c);
+            }
+            number.parse::<f64>().is_ok()
+        }
+        Some(_) => {
+            let mut word = String::new();
+            while let Some(c) = chars.next_if(char::is_ascii_alphabetic) {
+                word.push(c);
+            }
+            matches!(word.as_str(), "true" | "false" | "null")
+        }
+        None => false,
+    };
+    skip_whitespace(chars);
+    valid
+}
+
+/// The rest of an object or array, after its opening bracket
+fn json_members(chars: &mut Peekable<Chars>, close: char, keyed: bool) -> bool {
+    skip_whitespace(chars);
+    if chars.next_if_eq(&close).is_some() {
+        return true;
+    }
+    loop {
+        if keyed {
+            skip_whitespace(chars);
+            if !json_string(chars) {
+                return false;
+            }
+            skip_whitespace(chars);
+            if chars.next() != Some(':') {
+                return false;
+            }
+        }
+        if !json_value(chars) {
+            return false;
+        }
+        match chars.next() {
+            Some(',') => continue,
+            Some(c) => return c == close,
+            None => return false,
+        }
+    }
+}
+
+fn json_string(chars: &mut Peekable<Chars>) -> bool {
+    if chars.next() != Some('"') {
+        return false;
+    }
+    loop {
+        match chars.next() {
+            Some('"') => return true,
+            Some('\\') => match chars.next() {
+                Some('u') => {
+                    if !(0..4).all(|_| chars.next().is_some_and(|c| c.is_ascii_hexdigit())) {
+                        return false;
+                    }
+                }
+                Some(c) if "\"\\/bfnrt".contains(c) => {}
+                _ => return false,
+            },
+            Some(c) if c.is_control() => return false,
+            Some(_) => {}
+            None => return false,
+        }
+    }
+}
+
+fn skip_whitespace(chars: &mut Peekable<Chars>) {
+    while chars.next_if(|c| c.is_ascii_whitespace()).is_some() {}
+}